@@ -0,0 +1,796 @@
+//! Asynchronous Reddit API access.
+//!
+//! Requires the `async` feature. The [`AsyncApi`] struct mirrors
+//! the blocking [`Api`] struct, but its methods return futures
+//! instead of blocking the current thread, for use on a [tokio]
+//! runtime. Both share the same config, models, token handling,
+//! rate limiting, and retry policy.
+//!
+//! Models retrieved through an [`AsyncApi`] hold their own clone of
+//! it, so its futures are `'static` and can be spawned onto a runtime.
+//!
+//! [`AsyncApi`]: struct.AsyncApi.html
+//! [`Api`]: ../struct.Api.html
+//! [tokio]: https://docs.rs/tokio/0.1
+//!
+//! # Example
+//!
+//! ```rust,no_run,ignore
+//! use redbot::{async_api::AsyncApi, Config};
+//!
+//! fn main() {
+//!     let config = Config::load_config("config.json").expect("Could not load confiog");
+//!     let api = AsyncApi::new(config);
+//!     let task = api
+//!         .do_login()
+//!         .and_then(move |_| api.query_listing(QueryListingRequest::new("r/rust/hot", 10, 1)))
+//!         .map(|posts| println!("{:?}", posts))
+//!         .map_err(|err| eprintln!("{}", err));
+//!     tokio::run(task);
+//! }
+//! ```
+
+use crate::auth::ApplicationGrant;
+use crate::errors::{check_json_errors, ApiError};
+use crate::models::{subreddit::Subreddit, user::User};
+use crate::query_listing::{merge_params, Paging, QueryListingRequest};
+use crate::rate_limit::RateLimit;
+use crate::retry::{self, RetryPolicy};
+use crate::{
+    access_token_url, api_url, is_error_status, parse_access_token, read_rate_limit_headers,
+    request_headers, status_error, token_form, AccessTokenResponse, Config, Grant,
+};
+use futures::future::{self, Either, Loop};
+use futures::Future;
+use log::debug;
+use reqwest::header::{HeaderMap, USER_AGENT};
+use reqwest::r#async::{Client, RequestBuilder, Response};
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_timer::Delay;
+
+/// A boxed future resolving to a value from the API.
+pub type ApiFuture<'a, T> = Box<dyn Future<Item = T, Error = ApiError> + Send + 'a>;
+
+/// Asynchronous Reddit API access. This is the struct that you'll
+/// be using to interact with the API from a tokio runtime.
+///
+/// Cloning the struct is cheap, and clones share their access token,
+/// rate limit, and whoami info.
+#[derive(Clone)]
+pub struct AsyncApi {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    config: Config,
+    client: Client,
+    grant: Mutex<Grant>,
+    access_token: Mutex<Option<AccessTokenResponse>>,
+    rate_limit: Mutex<Option<RateLimit>>,
+    retry_policy: Mutex<RetryPolicy>,
+    whoami: Mutex<Option<Value>>,
+}
+
+impl AsyncApi {
+    /// Create a new API client.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - the configuration
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let config = Config::load_config().expect("Could not load config");
+    /// let api = AsyncApi::new(config);
+    /// ```
+    pub fn new(config: Config) -> Self {
+        debug!("New async API object created");
+        AsyncApi {
+            inner: Arc::new(Inner {
+                config,
+                client: Client::new(),
+                grant: Mutex::new(Grant::Password),
+                access_token: Mutex::new(None),
+                rate_limit: Mutex::new(None),
+                retry_policy: Mutex::new(RetryPolicy::default()),
+                whoami: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Uses the values from the config to get an access token
+    /// from the OAuth endpoint, and stores it in the struct.
+    ///
+    /// See [`Api::do_login`].
+    ///
+    /// [`Api::do_login`]: ../struct.Api.html#method.do_login
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let task = api.do_login().map_err(|err| panic!("Could not get an access token: {}", err));
+    /// ```
+    pub fn do_login(&self) -> ApiFuture<'static, ()> {
+        debug!("Performing login");
+        *self.inner.grant.lock().unwrap() = Grant::Password;
+        let api = self.clone();
+        Box::new(
+            self.refresh_access_token()
+                .and_then(move |_| api.load_whoami()),
+        )
+    }
+
+    /// Get an application-only access token, which isn't tied to any
+    /// user account, and store it in the struct.
+    ///
+    /// See [`Api::do_app_login`].
+    ///
+    /// [`Api::do_app_login`]: ../struct.Api.html#method.do_app_login
+    ///
+    /// # Arguments
+    ///
+    /// * `grant` - the [`ApplicationGrant`] to use
+    ///
+    /// [`ApplicationGrant`]: ../auth/enum.ApplicationGrant.html
+    pub fn do_app_login(&self, grant: ApplicationGrant) -> ApiFuture<'static, ()> {
+        debug!("Performing application-only login");
+        *self.inner.grant.lock().unwrap() = match grant {
+            ApplicationGrant::ClientCredentials => Grant::ClientCredentials,
+            ApplicationGrant::InstalledClient => Grant::InstalledClient,
+        };
+        *self.inner.whoami.lock().unwrap() = None;
+        self.refresh_access_token()
+    }
+
+    /// Load the account's whoami info into the struct.
+    fn load_whoami(&self) -> ApiFuture<'static, ()> {
+        let api = self.clone();
+        Box::new(self.get_whoami().map(move |whoami| {
+            debug!("Returned whoami is {:?}", whoami);
+            *api.inner.whoami.lock().unwrap() = Some(whoami);
+        }))
+    }
+
+    /// Get a new access token from the OAuth endpoint, using the grant
+    /// from the last login, and store it in the struct, replacing any
+    /// existing token.
+    fn refresh_access_token(&self) -> ApiFuture<'static, ()> {
+        let grant = *self.inner.grant.lock().unwrap();
+        let form = {
+            let token = self.inner.access_token.lock().unwrap();
+            match token_form(&self.inner.config, grant, token.as_ref()) {
+                Ok(form) => form,
+                Err(e) => return Box::new(future::err(e)),
+            }
+        };
+        debug!("Requesting access token with {} grant", form["grant_type"]);
+        let api = self.clone();
+        let config = &self.inner.config;
+        Box::new(
+            self.inner
+                .client
+                .post(&access_token_url())
+                .header(USER_AGENT, config.user_agent.clone())
+                .basic_auth(&config.client_id, Some(&config.client_secret))
+                .form(&form)
+                .send()
+                .and_then(|mut resp| {
+                    let status = resp.status();
+                    debug!("Access token response code = {}", status.as_str());
+                    resp.text().map(move |body| (status, body))
+                })
+                .map_err(ApiError::from)
+                .and_then(|(status, body)| parse_access_token(status, &body))
+                .map(move |data| {
+                    let mut token = api.inner.access_token.lock().unwrap();
+                    *token = Some(data.keep_refresh_token(token.as_ref()));
+                }),
+        )
+    }
+
+    /// Refresh the access token if there is one and it is
+    /// about to expire.
+    fn refresh_expiring_token(&self) -> ApiFuture<'static, ()> {
+        let expiring = match self.inner.access_token.lock().unwrap().as_ref() {
+            Some(token) => token.is_expiring(),
+            None => false,
+        };
+        if expiring {
+            debug!("Access token is about to expire");
+            self.refresh_access_token()
+        } else {
+            Box::new(future::ok(()))
+        }
+    }
+
+    /// Send a request, built by the `build` closure, to the API.
+    ///
+    /// The closure is called again to rebuild the request with new
+    /// headers if the access token had to be refreshed after the API
+    /// responded with a 401.
+    fn send<F>(&self, build: F) -> ApiFuture<'static, Response>
+    where
+        F: Fn(&AsyncApi) -> RequestBuilder + Send + Sync + 'static,
+    {
+        let build = Arc::new(build);
+        let api = self.clone();
+        let first = {
+            let api = self.clone();
+            let build = build.clone();
+            self.refresh_expiring_token()
+                .and_then(move |_| api.send_with_retries(build))
+        };
+        Box::new(first.and_then(move |resp| {
+            if resp.status() != StatusCode::UNAUTHORIZED
+                || api.inner.access_token.lock().unwrap().is_none()
+            {
+                return Either::A(future::ok(resp));
+            }
+            debug!("Access token rejected, refreshing and retrying");
+            let retry_api = api.clone();
+            Either::B(
+                api.refresh_access_token()
+                    .and_then(move |_| retry_api.send_with_retries(build)),
+            )
+        }))
+    }
+
+    /// Send a request to the API, retrying according to the retry policy
+    /// if it times out or the response status is a transient failure.
+    ///
+    /// The last response is returned if all attempts fail.
+    fn send_with_retries<F>(&self, build: Arc<F>) -> ApiFuture<'static, Response>
+    where
+        F: Fn(&AsyncApi) -> RequestBuilder + Send + Sync + 'static,
+    {
+        let api = self.clone();
+        Box::new(future::loop_fn(1, move |attempt| {
+            let api = api.clone();
+            let build = build.clone();
+            api.wait_for_rate_limit().and_then(move |_| {
+                let req = build(&api);
+                debug!("{:?}", req);
+                req.send().then(move |result| {
+                    if let Ok(resp) = &result {
+                        api.process_response_headers(resp.headers());
+                    }
+                    let policy = api.inner.retry_policy.lock().unwrap().clone();
+                    let delay = match &result {
+                        Ok(resp) if RetryPolicy::is_retryable(resp.status()) => {
+                            Some(policy.delay(attempt, Some(resp.headers())))
+                        }
+                        Err(e) if e.is_timeout() => Some(policy.delay(attempt, None)),
+                        _ => None,
+                    };
+                    match delay {
+                        Some(delay) if attempt < policy.max_attempts => {
+                            debug!("Attempt {} failed, retrying in {:?}", attempt, delay);
+                            Either::A(sleep(delay).map(move |_| Loop::Continue(attempt + 1)))
+                        }
+                        _ => Either::B(future::result(
+                            result.map(Loop::Break).map_err(ApiError::from),
+                        )),
+                    }
+                })
+            })
+        }))
+    }
+
+    /// Wait until there's room in the request budget, and take
+    /// a request from it.
+    fn wait_for_rate_limit(&self) -> ApiFuture<'static, ()> {
+        let api = self.clone();
+        Box::new(future::loop_fn((), move |_| {
+            let wait = {
+                let mut rate_limit = api.inner.rate_limit.lock().unwrap();
+                match rate_limit.as_mut() {
+                    Some(rl) if rl.is_exhausted() => rl.reset_in(),
+                    Some(rl) => {
+                        rl.reserve();
+                        return Either::A(future::ok(Loop::Break(())));
+                    }
+                    None => return Either::A(future::ok(Loop::Break(()))),
+                }
+            };
+            debug!("Rate limit exhausted, waiting {:?} for reset", wait);
+            Either::B(sleep(wait).map(Loop::Continue))
+        }))
+    }
+
+    /// Set the policy for retrying requests that fail for transient
+    /// reasons, like server errors. The policy is shared by all clones
+    /// of the client.
+    ///
+    /// See [`Api::set_retry_policy`].
+    ///
+    /// [`Api::set_retry_policy`]: ../struct.Api.html#method.set_retry_policy
+    pub fn set_retry_policy(&self, policy: RetryPolicy) {
+        *self.inner.retry_policy.lock().unwrap() = policy;
+    }
+
+    /// Returns the state of the request budget, as of the last response.
+    pub fn get_rate_limit(&self) -> Option<RateLimit> {
+        *self.inner.rate_limit.lock().unwrap()
+    }
+
+    /// Returns the account's whoami info, if logged in as a user.
+    pub fn whoami(&self) -> Option<Value> {
+        self.inner.whoami.lock().unwrap().clone()
+    }
+
+    /// Returns the account's username from the 'api/v1/me' endpoint.
+    pub fn get_whoami(&self) -> ApiFuture<'static, Value> {
+        Box::new(
+            self.query("GET", "api/v1/me", None, None)
+                .and_then(parse_json),
+        )
+    }
+
+    /// Returns the username from the stored whoami data.
+    pub fn get_username(&self) -> Option<String> {
+        Some(
+            self.inner.whoami.lock().unwrap().as_ref()?["name"]
+                .as_str()?
+                .to_owned(),
+        )
+    }
+
+    /// Generate headers for the request.
+    fn get_headers(&self) -> HeaderMap {
+        request_headers(
+            &self.inner.config,
+            self.inner.access_token.lock().unwrap().as_ref(),
+        )
+    }
+
+    /// Stores the rate limit values from the response headers, if present.
+    fn process_response_headers(&self, headers: &HeaderMap) {
+        if let Some(rl) = read_rate_limit_headers(headers) {
+            *self.inner.rate_limit.lock().unwrap() = Some(rl);
+        }
+    }
+
+    /// Turn error responses into the matching error.
+    fn check_status(&self, mut resp: Response) -> ApiFuture<'static, Response> {
+        let status = resp.status();
+        if !is_error_status(status) {
+            return Box::new(future::ok(resp));
+        }
+        let reset = retry::parse_retry_after(resp.headers())
+            .or_else(|| self.get_rate_limit().map(|rl| rl.reset_in()));
+        Box::new(
+            resp.text()
+                .then(move |body| Err(status_error(status, reset, body.unwrap_or_default()))),
+        )
+    }
+
+    /// Query the Reddit API.
+    ///
+    /// See [`Api::query`].
+    ///
+    /// [`Api::query`]: ../struct.Api.html#method.query
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let task = api
+    ///     .query("GET", "api/v1/me/karma", None, None)
+    ///     .and_then(|mut resp| resp.json::<Value>().map_err(ApiError::from));
+    /// ```
+    pub fn query(
+        &self,
+        method: &str,
+        path: &str,
+        query: Option<Vec<(&str, &str)>>,
+        form_data: Option<HashMap<&str, &str>>,
+    ) -> ApiFuture<'static, Response> {
+        let method = match Method::from_bytes(method.as_bytes()) {
+            Ok(method) => method,
+            Err(e) => return Box::new(future::err(e.into())),
+        };
+        let path = match api_url(path, self.get_username()) {
+            Ok(path) => path,
+            Err(e) => return Box::new(future::err(e)),
+        };
+        let query: Option<Vec<(String, String)>> = query.map(|q| {
+            q.into_iter()
+                .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                .collect()
+        });
+        let form_data: Option<HashMap<String, String>> = form_data.map(|fd| {
+            fd.into_iter()
+                .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                .collect()
+        });
+        let api = self.clone();
+        Box::new(
+            self.send(move |api| {
+                let req = api
+                    .inner
+                    .client
+                    .request(method.clone(), &path)
+                    .headers(api.get_headers());
+                let req = match &query {
+                    Some(q) => req.query(q),
+                    None => req,
+                };
+                match &form_data {
+                    Some(fd) => req.form(fd),
+                    None => req,
+                }
+            })
+            .and_then(move |resp| api.check_status(resp)),
+        )
+    }
+
+    /// Post form data to one of the API's `api/*` write endpoints.
+    ///
+    /// See [`Api::post_json`].
+    ///
+    /// [`Api::post_json`]: ../struct.Api.html#method.post_json
+    pub fn post_json(
+        &self,
+        path: &str,
+        mut form_data: HashMap<&str, &str>,
+    ) -> ApiFuture<'static, Value> {
+        form_data.insert("api_type", "json");
+        Box::new(
+            self.query("POST", path, None, Some(form_data))
+                .and_then(parse_json)
+                .and_then(|data: Value| {
+                    check_json_errors(&data)?;
+                    Ok(data)
+                }),
+        )
+    }
+
+    /// Query the Reddit API via a listing endpoint.
+    ///
+    /// See [`Api::query_listing`].
+    ///
+    /// [`Api::query_listing`]: ../struct.Api.html#method.query_listing
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let ql = QueryListingRequest::new("r/rust/hot", 1, 1);
+    /// let task = api.query_listing(ql).map(|data| println!("{:?}", data));
+    /// ```
+    pub fn query_listing(&self, ql: QueryListingRequest) -> ApiFuture<'static, Vec<Value>> {
+        debug!("Listing request call: {:?}", ql);
+        let requests = ql.requests;
+        self.get_listing(ql, requests, u64::MAX)
+    }
+
+    /// Get the first `count` items of a listing, requesting pages
+    /// until there are enough or the listing runs out, so
+    /// `ql.requests` is ignored, like for [`Api::listing_iter`].
+    ///
+    /// [`Api::listing_iter`]: ../struct.Api.html#method.listing_iter
+    pub(crate) fn listing_take(
+        &self,
+        ql: QueryListingRequest,
+        count: u64,
+    ) -> ApiFuture<'static, Vec<Value>> {
+        debug!("Listing request for {} items: {:?}", count, ql);
+        Box::new(self.get_listing(ql, u64::MAX, count).map(move |mut items| {
+            items.truncate(count as usize);
+            items
+        }))
+    }
+
+    /// Get a listing's items a page at a time, until `requests` pages
+    /// were requested, at least `max_items` items were received, or
+    /// the listing runs out.
+    fn get_listing(
+        &self,
+        ql: QueryListingRequest,
+        requests: u64,
+        max_items: u64,
+    ) -> ApiFuture<'static, Vec<Value>> {
+        let path = match api_url(ql.path, self.get_username()) {
+            Ok(path) => path,
+            Err(e) => return Box::new(future::err(e)),
+        };
+        if requests == 0 || max_items == 0 {
+            return Box::new(future::ok(Vec::new()));
+        }
+        let custom: Vec<(String, String)> = ql
+            .params
+            .iter()
+            .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+            .collect();
+        let paging = ql.paging();
+        let api = self.clone();
+
+        Box::new(future::loop_fn(
+            (Vec::new(), paging, 1),
+            move |(mut all_resp, mut paging, request): (Vec<Value>, Paging, u64)| {
+                let params = merge_params(
+                    custom.iter().map(|(k, v)| (k.as_str(), v.as_str())),
+                    paging.page_params(),
+                );
+                let cursor_param = paging.cursor_param();
+                let path = path.clone();
+                let status_api = api.clone();
+                api.send(move |api| {
+                    api.inner
+                        .client
+                        .request(Method::GET, &path)
+                        .headers(api.get_headers())
+                        .query(&params)
+                })
+                .and_then(move |resp| status_api.check_status(resp))
+                .and_then(parse_json)
+                .map(move |data: Value| {
+                    let cursor = data["data"][cursor_param]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_owned();
+                    let items = match data["data"]["children"].as_array() {
+                        Some(children) => children.clone(),
+                        None => Vec::new(),
+                    };
+                    let empty = items.is_empty();
+                    paging.count += items.len() as u64;
+                    all_resp.extend(items);
+                    if request >= requests
+                        || all_resp.len() as u64 >= max_items
+                        || empty
+                        || cursor.is_empty()
+                    {
+                        Loop::Break(all_resp)
+                    } else {
+                        paging.cursor = cursor;
+                        Loop::Continue((all_resp, paging, request + 1))
+                    }
+                })
+            },
+        ))
+    }
+
+    /// Search for subreddits matching the parameter.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - subreddit (partial) name
+    pub fn search_for_subreddit(
+        &self,
+        name: &str,
+    ) -> ApiFuture<'static, Vec<Subreddit<'static, AsyncApi>>> {
+        let api = self.clone();
+        Box::new(
+            self.query(
+                "GET",
+                "api/search_reddit_names",
+                Some(vec![("query", name), ("exact", "false")]),
+                None,
+            )
+            .and_then(parse_json)
+            .map(move |data: Value| {
+                data["names"]
+                    .as_array()
+                    .map(|names| {
+                        names
+                            .iter()
+                            .filter_map(|v| v.as_str())
+                            .map(|e| Subreddit {
+                                api: api.clone(),
+                                name: e.to_owned(),
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            }),
+        )
+    }
+
+    /// Get a subreddit by its name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - subreddit name
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let task = api
+    ///     .get_subreddit("rust")
+    ///     .and_then(|sr| sr.get_top(10, None));
+    /// ```
+    pub fn get_subreddit(&self, name: &str) -> ApiFuture<'static, Subreddit<'static, AsyncApi>> {
+        let name = name.to_owned();
+        Box::new(self.search_for_subreddit(&name).and_then(move |matching| {
+            matching
+                .into_iter()
+                .find(|sr| sr.name == name)
+                .ok_or_else(|| ApiError::Other(String::from("Subreddit not found")))
+        }))
+    }
+
+    /// Get a user by their name.
+    ///
+    /// Queries the user's "about" page to verify valid username.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - username
+    pub fn get_user(&self, name: &str) -> ApiFuture<'static, User<'static, AsyncApi>> {
+        let api = self.clone();
+        Box::new(
+            self.query("GET", &format!("user/{}/about", name), None, None)
+                .and_then(parse_json)
+                .and_then(move |data: Value| User::from_about(api, &data)),
+        )
+    }
+}
+
+/// Deserialize the response's body as JSON.
+fn parse_json<T>(mut resp: Response) -> ApiFuture<'static, T>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    Box::new(
+        resp.text()
+            .map_err(ApiError::from)
+            .and_then(|body| Ok(serde_json::from_str(&body)?)),
+    )
+}
+
+/// Wait for the duration without blocking the thread.
+fn sleep(duration: Duration) -> impl Future<Item = (), Error = ApiError> {
+    Delay::new(Instant::now() + duration)
+        .map_err(|e| ApiError::Other(format!("Timer error: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AsyncApi;
+    use crate::errors::ApiError;
+    use crate::models::post::tests::sample_item;
+    use crate::query_listing::QueryListingRequest;
+    use crate::retry::RetryPolicy;
+    use futures::Future;
+    use mockito::mock;
+    use std::time::Duration;
+    use tokio::runtime::current_thread::Runtime;
+
+    fn get_api() -> AsyncApi {
+        AsyncApi::new(std::default::Default::default())
+    }
+
+    fn run<F: Future>(future: F) -> Result<F::Item, F::Error> {
+        Runtime::new().unwrap().block_on(future)
+    }
+
+    #[test]
+    fn do_login() {
+        let _m1 = mock("POST", "/api/v1/access_token")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                "{\"access_token\":\"aaaaa\",\"token_type\":\"bearer\", \
+                 \"expires_in\":3600,\"scope\":\"*\"}",
+            )
+            .create();
+        let _m2 = mock("GET", "/api/v1/me")
+            .match_header("authorization", "bearer aaaaa")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body("{\"name\":\"test-name\"}")
+            .create();
+
+        let api = get_api();
+        run(api.do_login()).unwrap();
+
+        assert_eq!(api.get_username().unwrap(), "test-name");
+        _m1.assert();
+        _m2.assert();
+    }
+
+    #[test]
+    fn query_error() {
+        let _m1 = mock("GET", "/some/endpoint")
+            .with_status(503)
+            .with_body("down")
+            .expect(2)
+            .create();
+
+        let api = get_api();
+        api.set_retry_policy(RetryPolicy::new(2).base_delay(Duration::from_millis(1)));
+        match run(api.query("GET", "some/endpoint", None, None)) {
+            Err(ApiError::Http { status, body }) => {
+                assert_eq!(status, 503);
+                assert_eq!(body, "down");
+            }
+            _ => panic!("Expected HTTP error"),
+        }
+
+        _m1.assert();
+    }
+
+    #[test]
+    fn query_listing() {
+        let body = "{\"data\":{\"kind\":\"Listing\",\"after\":null,\"children\": \
+                    [{\"data\":{\"id\":\"aaaaa\"},\"kind\":\"t3\"},{\"data\":{\"id\":\"bbbbb\"}, \
+                    \"kind\":\"t3\"}]}}";
+        let _m1 = mock("GET", "/some/endpoint?limit=3&show=all")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .create();
+
+        let ql = QueryListingRequest::new("some/endpoint", 3, 2);
+        let values = run(get_api().query_listing(ql)).unwrap();
+
+        assert_eq!(values.len(), 2);
+        _m1.assert();
+    }
+
+    #[test]
+    fn get_subreddit() {
+        let body = "{\"names\":[\"rust\",\"rust1\",\"rust2\"]}";
+        let _m1 = mock("GET", "/api/search_reddit_names?query=rust1&exact=false")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .create();
+
+        let api = get_api();
+        let sr = run(api.get_subreddit("rust1")).unwrap();
+
+        assert_eq!(sr.name, "rust1");
+        _m1.assert();
+    }
+
+    #[test]
+    fn get_subreddit_static() {
+        let _m1 = mock("GET", "/api/search_reddit_names?query=rust&exact=false")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body("{\"names\":[\"rust\"]}")
+            .create();
+        let _m2 = mock("GET", "/r/rust/hot?limit=1&show=all")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(format!(
+                "{{\"data\":{{\"after\":null,\"children\":[{}]}}}}",
+                sample_item("aaaaa")
+            ))
+            .create();
+
+        // the multi-threaded runtime needs a `'static` future, which
+        // can outlive the client it came from
+        let task = get_api().get_subreddit("rust").and_then(|sr| sr.get_hot(1));
+        let posts = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(task)
+            .unwrap();
+
+        assert_eq!(posts.len(), 1);
+        assert_eq!(posts[0].data.id, "aaaaa");
+        _m1.assert();
+        _m2.assert();
+    }
+
+    #[test]
+    fn get_user() {
+        let _m1 = mock("GET", "/user/test/about")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body("{\"kind\":\"t2\",\"data\":{\"name\":\"test\"}}")
+            .create();
+
+        let api = get_api();
+        let user = run(api.get_user("test")).unwrap();
+
+        assert_eq!(user.name(), "test");
+        _m1.assert();
+    }
+}
@@ -0,0 +1,145 @@
+//! Structs for use in the [OAuth] authorization code flow, used
+//! by 'web app' and 'installed app' type applications to act on
+//! behalf of other users, and for [application-only] logins.
+//!
+//! [OAuth]: https://github.com/reddit-archive/reddit/wiki/OAuth2
+//! [application-only]: https://github.com/reddit-archive/reddit/wiki/OAuth2#application-only-oauth
+//!
+//! # Examples
+//!
+//! Application-only:
+//!
+//! ```rust,no_run,ignore
+//! api.do_app_login(ApplicationGrant::ClientCredentials)?;
+//! ```
+//!
+//! Authorization code flow:
+//!
+//! ```rust,no_run,ignore
+//! let ar = AuthorizationRequest::new(&["identity", "read"], "some-state")
+//!     .duration(TokenDuration::Permanent);
+//! let url = api.get_authorization_url(ar)?;
+//! // send the user to `url`, then receive the code at the redirect URI
+//! api.do_code_login(&code)?;
+//! ```
+
+/// The grant types for application-only logins.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ApplicationGrant {
+    /// For confidential clients, like 'web app' and 'script' type
+    /// applications, that can keep their client secret.
+    ClientCredentials,
+    /// For 'installed app' type applications, which have no client
+    /// secret. Uses the `device_id` from the config.
+    InstalledClient,
+}
+
+/// How long the access granted by the user should last.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TokenDuration {
+    /// A single access token that expires after an hour.
+    Temporary,
+    /// An access token along with a refresh token that
+    /// can be used to get new access tokens indefinitely.
+    Permanent,
+}
+
+impl TokenDuration {
+    /// The value to pass to the authorize endpoint.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TokenDuration::Temporary => "temporary",
+            TokenDuration::Permanent => "permanent",
+        }
+    }
+}
+
+/// Builder struct for constructing the URL to send a user to
+/// in order to authorize the application.
+#[derive(Clone, Debug)]
+pub struct AuthorizationRequest<'a> {
+    /// The scopes to request
+    pub scopes: &'a [&'a str],
+    /// A unique, random string to verify the redirect with
+    pub state: &'a str,
+    /// How long the access should last
+    pub duration: TokenDuration,
+    /// Whether to show the mobile-friendly authorization page
+    pub compact: bool,
+}
+
+impl<'a> AuthorizationRequest<'a> {
+    /// Construct a new builder.
+    pub fn new(scopes: &'a [&'a str], state: &'a str) -> Self {
+        AuthorizationRequest {
+            scopes,
+            state,
+            duration: TokenDuration::Temporary,
+            compact: false,
+        }
+    }
+
+    /// Override the `scopes` field.
+    pub fn scopes(mut self, scopes: &'a [&'a str]) -> Self {
+        self.scopes = scopes;
+        self
+    }
+
+    /// Override the `state` field.
+    pub fn state(mut self, state: &'a str) -> Self {
+        self.state = state;
+        self
+    }
+
+    /// Override the `duration` field.
+    pub fn duration(mut self, duration: TokenDuration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Override the `compact` field.
+    pub fn compact(mut self, compact: bool) -> Self {
+        self.compact = compact;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AuthorizationRequest, TokenDuration};
+
+    #[test]
+    fn simple() {
+        let scopes = ["identity"];
+        let state = "s";
+
+        let ar = AuthorizationRequest::new(&scopes, state);
+
+        assert_eq!(ar.scopes, &scopes);
+        assert_eq!(ar.state, state);
+        assert_eq!(ar.duration, TokenDuration::Temporary);
+        assert!(!ar.compact);
+    }
+
+    #[test]
+    fn with_builders() {
+        let scopes = ["identity", "read"];
+
+        let ar = AuthorizationRequest::new(&[], "a")
+            .scopes(&scopes)
+            .state("b")
+            .duration(TokenDuration::Permanent)
+            .compact(true);
+
+        assert_eq!(ar.scopes, &scopes);
+        assert_eq!(ar.state, "b");
+        assert_eq!(ar.duration, TokenDuration::Permanent);
+        assert!(ar.compact);
+    }
+
+    #[test]
+    fn duration_as_str() {
+        assert_eq!(TokenDuration::Temporary.as_str(), "temporary");
+        assert_eq!(TokenDuration::Permanent.as_str(), "permanent");
+    }
+}
@@ -1,122 +1,399 @@
 //! Error handling.
 //!
-//! Includes a custom error type `ApiError` that
-//! contains conversions from the underlying error types that the
+//! Includes a custom error type `ApiError` with a variant for each
+//! kind of problem that can occur when communicating with the API,
+//! and conversions from the underlying error types that the
 //! libraries that this library relies on can generate.
+//!
+//! # Examples
+//!
+//! ```rust,no_run,ignore
+//! match api.get_user("some-username") {
+//!     Ok(user) => println!("{}", user.name()),
+//!     Err(ApiError::Http { status: 404, .. }) => println!("No such user"),
+//!     Err(err) => panic!(err),
+//! }
+//! ```
 
-use reqwest;
+use serde_json::Value;
 use std::error;
 use std::fmt;
+use std::time::Duration;
 
-/// Wrapper for errors.
-#[derive(Debug, PartialEq)]
-pub struct ApiError {
-    /// Source of the error.
-    pub source: String,
-    /// Message that details the problem.
+/// Errors that can occur when using the API.
+#[derive(Debug)]
+pub enum ApiError {
+    /// The API responded with an error status code.
+    Http {
+        /// The response's status code
+        status: u16,
+        /// The response's body
+        body: String,
+    },
+    /// The API refused the request due to rate limiting.
+    RateLimited {
+        /// How long until requests will be accepted again, if known
+        reset: Option<Duration>,
+    },
+    /// Getting an access token failed, or the API rejected it.
+    Auth(String),
+    /// The API returned errors in the body of an otherwise successful response.
+    Reddit(Vec<RedditError>),
+    /// A response could not be deserialized.
+    Deserialize(serde_json::Error),
+    /// Reading a file failed.
+    Io(std::io::Error),
+    /// The config is missing a value required for the operation.
+    Config(String),
+    /// The request could not be sent.
+    Request(reqwest::Error),
+    /// The subreddit has no wiki page with the name.
+    WikiPageNotFound {
+        /// The page's name
+        page: String,
+    },
+    /// The account may not view or edit the wiki page.
+    WikiPermissionDenied {
+        /// The page's name
+        page: String,
+        /// The reason given by the API, i.e. "MAY_NOT_VIEW"
+        reason: String,
+    },
+    /// Any other problem, like an item not being found.
+    Other(String),
+}
+
+/// A single error from the `json.errors` array that Reddit
+/// returns from its `api/*` endpoints.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RedditError {
+    /// The error code, i.e. "RATELIMIT"
+    pub code: String,
+    /// The human-readable message
     pub message: String,
+    /// The form field the error applies to, if any
+    pub field: Option<String>,
+}
+
+impl RedditError {
+    /// Parse a single `[code, message, field]` error array.
+    fn from_value(value: &Value) -> Option<Self> {
+        let parts = value.as_array()?;
+        Some(RedditError {
+            code: parts.first()?.as_str()?.to_owned(),
+            message: parts.get(1)?.as_str().unwrap_or_default().to_owned(),
+            field: parts.get(2).and_then(Value::as_str).map(str::to_owned),
+        })
+    }
+
+    /// For "RATELIMIT" errors, parse how long to wait from the
+    /// message, i.e. "you are doing that too much. try again in 5 minutes."
+    pub fn ratelimit_duration(&self) -> Option<Duration> {
+        if self.code != "RATELIMIT" {
+            return None;
+        }
+        let rest = &self.message[self.message.find("try again in ")? + 13..];
+        let mut words = rest.split_whitespace();
+        let amount = words.next()?.parse::<u64>().ok()?;
+        let unit = words.next()?.trim_end_matches('.');
+        let seconds = match unit.trim_end_matches('s') {
+            "millisecond" => return Some(Duration::from_millis(amount)),
+            "second" => amount,
+            "minute" => amount * 60,
+            "hour" => amount * 60 * 60,
+            _ => return None,
+        };
+        Some(Duration::from_secs(seconds))
+    }
+}
+
+/// Check the body of a response from an `api/*` endpoint for
+/// the `json.errors` array, which Reddit uses to report problems
+/// with otherwise successful requests.
+///
+/// "RATELIMIT" errors are returned as `ApiError::RateLimited`,
+/// and any others as `ApiError::Reddit`.
+///
+/// # Arguments
+///
+/// * `data` - the response body
+///
+/// # Examples
+///
+/// ```rust,no_run,ignore
+/// let data: Value = resp.json()?;
+/// check_json_errors(&data)?;
+/// ```
+pub fn check_json_errors(data: &Value) -> Result<(), ApiError> {
+    let errors: Vec<RedditError> = match data["json"]["errors"].as_array() {
+        Some(errors) => errors.iter().filter_map(RedditError::from_value).collect(),
+        None => return Ok(()),
+    };
+    if errors.is_empty() {
+        return Ok(());
+    }
+    if let Some(e) = errors.iter().find(|e| e.code == "RATELIMIT") {
+        return Err(ApiError::RateLimited {
+            reset: e.ratelimit_duration(),
+        });
+    }
+    Err(ApiError::Reddit(errors))
+}
+
+impl fmt::Display for RedditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.field {
+            Some(field) => write!(f, "{}: {} ({})", self.code, self.message, field),
+            None => write!(f, "{}: {}", self.code, self.message),
+        }
+    }
 }
 
 impl fmt::Display for ApiError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if self.source.is_empty() {
-            write!(f, "API error: {}", self.message)
-        } else {
-            write!(f, "API error from '{}': {}", self.source, self.message)
+        match self {
+            ApiError::Http { status, .. } => write!(f, "API error: error code {}", status),
+            ApiError::RateLimited { reset: Some(reset) } => write!(
+                f,
+                "API error: rate limited, reset in {} seconds",
+                reset.as_secs()
+            ),
+            ApiError::RateLimited { reset: None } => write!(f, "API error: rate limited"),
+            ApiError::Auth(message) => write!(f, "API error: authentication failed: {}", message),
+            ApiError::Reddit(errors) => {
+                let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                write!(f, "API error from 'reddit': {}", messages.join(", "))
+            }
+            ApiError::Deserialize(error) => write!(f, "API error from 'serde_json': {}", error),
+            ApiError::Io(error) => write!(f, "API error from 'std::io': {}", error),
+            ApiError::Config(message) => write!(f, "API error: config: {}", message),
+            ApiError::Request(error) => write!(f, "API error from 'reqwest': {}", error),
+            ApiError::WikiPageNotFound { page } => {
+                write!(f, "API error: wiki page '{}' not found", page)
+            }
+            ApiError::WikiPermissionDenied { page, reason } => write!(
+                f,
+                "API error: permission denied for wiki page '{}': {}",
+                page, reason
+            ),
+            ApiError::Other(message) => write!(f, "API error: {}", message),
         }
     }
 }
 
 impl error::Error for ApiError {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
-        None
+        match self {
+            ApiError::Deserialize(error) => Some(error),
+            ApiError::Io(error) => Some(error),
+            ApiError::Request(error) => Some(error),
+            _ => None,
+        }
     }
 }
 
 impl From<reqwest::Error> for ApiError {
     fn from(error: reqwest::Error) -> Self {
-        ApiError {
-            source: "reqwest::Error".to_owned(),
-            message: format!("{:?}", error).to_owned(),
-        }
+        ApiError::Request(error)
     }
 }
 
 impl From<std::io::Error> for ApiError {
     fn from(error: std::io::Error) -> Self {
-        ApiError {
-            source: "std::io::Error".to_owned(),
-            message: format!("{:?}", error).to_owned(),
-        }
+        ApiError::Io(error)
     }
 }
 
 impl From<serde_json::error::Error> for ApiError {
     fn from(error: serde_json::error::Error) -> Self {
-        ApiError {
-            source: "serde_json::error::Error".to_owned(),
-            message: format!("{:?}", error).to_owned(),
-        }
+        ApiError::Deserialize(error)
     }
 }
 
 impl From<http::method::InvalidMethod> for ApiError {
     fn from(error: http::method::InvalidMethod) -> Self {
-        ApiError {
-            source: "http::method::InvalidMethod".to_owned(),
-            message: format!("{:?}", error).to_owned(),
-        }
+        ApiError::Other(format!("{}", error))
     }
 }
 
 impl From<String> for ApiError {
     fn from(error: String) -> Self {
-        ApiError {
-            source: String::new(),
-            message: error,
-        }
+        ApiError::Other(error)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::ApiError;
+    use super::{check_json_errors, ApiError, RedditError};
+    use serde_json::json;
+    use std::error::Error;
+    use std::time::Duration;
 
     #[test]
     fn from_string() {
         let msg = String::from("some erorr");
-        let expected = ApiError {
-            source: String::new(),
-            message: msg.clone(),
+        let actual = ApiError::from(msg.clone());
+
+        match actual {
+            ApiError::Other(message) => assert_eq!(message, msg),
+            _ => panic!("Wrong variant"),
+        }
+    }
+
+    #[test]
+    fn display_other() {
+        let e = ApiError::Other(String::from("something"));
+        let expected = String::from("API error: something");
+        let actual = format!("{}", e);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn display_http() {
+        let e = ApiError::Http {
+            status: 404,
+            body: String::new(),
         };
-        let actual = ApiError::from(msg);
+        let expected = String::from("API error: error code 404");
+        let actual = format!("{}", e);
 
         assert_eq!(actual, expected);
     }
 
     #[test]
-    fn display_no_source() {
-        let e = ApiError {
-            source: String::new(),
-            message: String::from("something"),
+    fn display_rate_limited() {
+        let e = ApiError::RateLimited {
+            reset: Some(Duration::from_secs(60)),
         };
-        let expected = String::from("API error: something");
+        let expected = String::from("API error: rate limited, reset in 60 seconds");
         let actual = format!("{}", e);
 
         assert_eq!(actual, expected);
     }
 
     #[test]
-    fn display_with_source() {
-        let e = ApiError {
-            source: String::from("somewhere"),
-            message: String::from("something"),
+    fn display_wiki() {
+        let not_found = ApiError::WikiPageNotFound {
+            page: String::from("config/bot"),
+        };
+        let denied = ApiError::WikiPermissionDenied {
+            page: String::from("config/bot"),
+            reason: String::from("MAY_NOT_VIEW"),
         };
-        let expected = String::from("API error from 'somewhere': something");
+
+        assert_eq!(
+            format!("{}", not_found),
+            "API error: wiki page 'config/bot' not found"
+        );
+        assert_eq!(
+            format!("{}", denied),
+            "API error: permission denied for wiki page 'config/bot': MAY_NOT_VIEW"
+        );
+    }
+
+    #[test]
+    fn display_reddit() {
+        let e = ApiError::Reddit(vec![
+            RedditError {
+                code: String::from("NO_TEXT"),
+                message: String::from("we need something here"),
+                field: Some(String::from("title")),
+            },
+            RedditError {
+                code: String::from("USER_REQUIRED"),
+                message: String::from("please login to do that"),
+                field: None,
+            },
+        ]);
+        let expected = String::from(
+            "API error from 'reddit': NO_TEXT: we need something here (title), \
+             USER_REQUIRED: please login to do that",
+        );
         let actual = format!("{}", e);
 
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn source() {
+        let json_error = serde_json::from_str::<u64>("a").unwrap_err();
+        let e = ApiError::from(json_error);
+
+        assert!(e.source().is_some());
+        assert!(ApiError::Auth(String::from("something")).source().is_none());
+    }
+
+    #[test]
+    fn check_json_errors_none() {
+        assert!(check_json_errors(&json!({"json": {"errors": []}})).is_ok());
+        assert!(check_json_errors(&json!({"json": {"data": {}}})).is_ok());
+        assert!(check_json_errors(&json!({"kind": "Listing"})).is_ok());
+    }
+
+    #[test]
+    fn check_json_errors_reddit() {
+        let data = json!({"json": {"errors": [
+            ["NO_TEXT", "we need something here", "title"],
+            ["USER_REQUIRED", "please login to do that", null],
+        ]}});
+
+        match check_json_errors(&data) {
+            Err(ApiError::Reddit(errors)) => {
+                assert_eq!(errors.len(), 2);
+                assert_eq!(errors[0].code, "NO_TEXT");
+                assert_eq!(errors[0].field, Some(String::from("title")));
+                assert_eq!(errors[1].message, "please login to do that");
+                assert_eq!(errors[1].field, None);
+            }
+            _ => panic!("Expected Reddit error"),
+        }
+    }
+
+    #[test]
+    fn check_json_errors_ratelimit() {
+        let data = json!({"json": {"errors": [
+            ["RATELIMIT", "you are doing that too much. try again in 9 minutes.", "ratelimit"],
+        ]}});
+
+        match check_json_errors(&data) {
+            Err(ApiError::RateLimited { reset }) => {
+                assert_eq!(reset, Some(Duration::from_secs(540)))
+            }
+            _ => panic!("Expected rate limited error"),
+        }
+    }
+
+    #[test]
+    fn ratelimit_duration() {
+        let error = |code: &str, message: &str| RedditError {
+            code: code.to_owned(),
+            message: message.to_owned(),
+            field: None,
+        };
+
+        assert_eq!(
+            error("RATELIMIT", "try again in 1 minute.").ratelimit_duration(),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            error(
+                "RATELIMIT",
+                "Take a break for 30 seconds. try again in 30 seconds."
+            )
+            .ratelimit_duration(),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            error("RATELIMIT", "try again in 2 hours").ratelimit_duration(),
+            Some(Duration::from_secs(7200))
+        );
+        assert_eq!(error("RATELIMIT", "slow down").ratelimit_duration(), None);
+        assert_eq!(
+            error("NO_TEXT", "try again in 1 minute.").ratelimit_duration(),
+            None
+        );
+    }
+
     // 'From' impl's tested by nature of successfully compiling
 }
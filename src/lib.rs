@@ -38,30 +38,55 @@
 
 use log::debug;
 
-use reqwest::Method;
+use reqwest::{Method, StatusCode};
 use reqwest::{
     self,
     header::{HeaderMap, HeaderValue, AUTHORIZATION, USER_AGENT},
 };
-use serde::Deserialize;
+use reqwest::Url;
+use serde::{de::DeserializeOwned, Deserialize};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
 use std::{fs::File, io::prelude::*};
 
 pub mod query_listing;
-use query_listing::QueryListingRequest;
+use query_listing::{ListingIter, Paging, QueryListingRequest};
+
+pub mod auth;
+use auth::{ApplicationGrant, AuthorizationRequest};
+
+pub mod rate_limit;
+use rate_limit::RateLimit;
+
+pub mod retry;
+use retry::RetryPolicy;
+
+#[cfg(feature = "async")]
+pub mod async_api;
 
 pub mod errors;
-use errors::ApiError;
+use errors::{check_json_errors, ApiError};
 pub mod models;
-use models::{subreddit::Subreddit, user::User};
+use models::{
+    comment::CommentTree, message::Inbox, modmail::Modmail, post::Post, subreddit::Subreddit,
+    user::User,
+};
 
 const RATE_LIMIT_HEADER_NAMES: [&str; 3] = [
-    "X-Ratelimit-Used",
-    "X-Ratelimit-Remaining",
-    "X-Ratelimit-Reset",
+    rate_limit::USED_HEADER,
+    rate_limit::REMAINING_HEADER,
+    rate_limit::RESET_HEADER,
 ];
 
+/// Device id sent with installed client grants when none is configured.
+const DEFAULT_DEVICE_ID: &str = "DO_NOT_TRACK_THIS_DEVICE";
+
+/// How long before the access token's expiry to request a new one.
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
 /// Program configuration - contains the required values
 /// to communicate with the Reddit OAuth API for a token.
 ///
@@ -74,25 +99,43 @@ const RATE_LIMIT_HEADER_NAMES: [&str; 3] = [
 /// 'script' type application that you create on the [Reddit
 /// website].
 ///
+/// 'web app' and 'installed app' type applications, which act on
+/// behalf of other users through the authorization code flow, don't
+/// need the `username` and `password` fields, but do need the
+/// `redirect_uri` field to match the one set for the application.
+/// 'installed app' type applications have no `client_secret`.
+///
+/// Application-only logins, which don't act on behalf of any user,
+/// only need the app's `client_id` and `client_secret`, and the
+/// optional `device_id` for 'installed app' type applications.
+///
 /// [API usage requirements]: https://github.com/reddit-archive/reddit/wiki/API#rules
 /// [Reddit website]: https://www.reddit.com/prefs/apps/
 #[derive(Debug, Deserialize, PartialEq, Clone)]
 #[cfg_attr(test, derive(Default))]
 pub struct Config {
     /// Account username
+    #[serde(default)]
     pub username: String,
     /// Account password
+    #[serde(default)]
     pub password: String,
     /// User agent to use
     pub user_agent: String,
     /// App client id
     pub client_id: String,
     /// App client secret
+    #[serde(default)]
     pub client_secret: String,
+    /// App redirect URI, for the authorization code flow
+    #[serde(default)]
+    pub redirect_uri: Option<String>,
+    /// Unique device id, for application-only logins by installed apps
+    #[serde(default)]
+    pub device_id: Option<String>,
 }
 
 impl Config {
-
     /// Attempt to load the configuration from a file.
     ///
     /// # Arguments
@@ -132,7 +175,10 @@ impl Config {
 pub struct Api {
     config: Config,
     client: reqwest::Client,
-    access_token: Option<AccessTokenResponse>,
+    grant: Grant,
+    access_token: Mutex<Option<AccessTokenResponse>>,
+    rate_limit: Mutex<Option<RateLimit>>,
+    retry_policy: RetryPolicy,
     /// The account's whoami info
     pub whoami: Option<Value>,
 }
@@ -155,7 +201,10 @@ impl Api {
         Api {
             config,
             client: reqwest::Client::new(),
-            access_token: None,
+            grant: Grant::Password,
+            access_token: Mutex::new(None),
+            rate_limit: Mutex::new(None),
+            retry_policy: RetryPolicy::default(),
             whoami: None,
         }
     }
@@ -166,6 +215,9 @@ impl Api {
     /// This method should be called after creating the struct,
     /// and before attempting to query any inforamtion from the API.
     ///
+    /// The token is automatically refreshed when it is about to
+    /// expire, or if the API rejects it.
+    ///
     /// # Examples
     ///
     /// ```rust,no_run,ignore
@@ -174,34 +226,304 @@ impl Api {
     /// }
     /// ```
     pub fn do_login(&mut self) -> Result<(), ApiError> {
-        // urls
-        #[cfg(not(test))]
-        let url = "https://www.reddit.com";
-        #[cfg(test)]
-        let url = &mockito::server_url();
-
         debug!("Performing login");
+        self.grant = Grant::Password;
+        self.refresh_access_token()?;
+        self.load_whoami()
+    }
+
+    /// Get an application-only access token, which isn't tied to any
+    /// user account, and store it in the struct.
+    ///
+    /// This is an alternative to [`do_login`] for read-only access to the
+    /// API that doesn't need the `username` and `password` fields from the
+    /// config. As there is no account, there is no whoami info, and paths
+    /// using the `{username}` macro can't be queried.
+    ///
+    /// [`do_login`]: #method.do_login
+    ///
+    /// # Arguments
+    ///
+    /// * `grant` - the [`ApplicationGrant`] to use
+    ///
+    /// [`ApplicationGrant`]: auth/enum.ApplicationGrant.html
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// if let Err(err) = api.do_app_login(ApplicationGrant::ClientCredentials) {
+    ///     panic!("Could not get an access token: {}", err);
+    /// }
+    /// ```
+    pub fn do_app_login(&mut self, grant: ApplicationGrant) -> Result<(), ApiError> {
+        debug!("Performing application-only login");
+        self.grant = match grant {
+            ApplicationGrant::ClientCredentials => Grant::ClientCredentials,
+            ApplicationGrant::InstalledClient => Grant::InstalledClient,
+        };
+        self.whoami = None;
+        self.refresh_access_token()
+    }
+
+    /// Build the URL to send a user to in order for them to authorize
+    /// the application to act on their behalf.
+    ///
+    /// After the user accepts, Reddit redirects them to the `redirect_uri`
+    /// from the config with `state` and `code` query parameters. Check that
+    /// the state matches, and pass the code to [`do_code_login`].
+    ///
+    /// [`do_code_login`]: #method.do_code_login
+    ///
+    /// # Arguments
+    ///
+    /// * `ar` - An [`AuthorizationRequest`] struct
+    ///
+    /// [`AuthorizationRequest`]: auth/struct.AuthorizationRequest.html
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let ar = AuthorizationRequest::new(&["identity", "read"], "some-state")
+    ///     .duration(TokenDuration::Permanent);
+    /// let url = api.get_authorization_url(ar)?;
+    /// ```
+    pub fn get_authorization_url(&self, ar: AuthorizationRequest) -> Result<String, ApiError> {
+        let redirect_uri = self.get_redirect_uri()?;
+        let path = if ar.compact {
+            "api/v1/authorize.compact"
+        } else {
+            "api/v1/authorize"
+        };
+        let url = Url::parse_with_params(
+            &format!("https://www.reddit.com/{}", path),
+            &[
+                ("client_id", self.config.client_id.as_str()),
+                ("response_type", "code"),
+                ("state", ar.state),
+                ("redirect_uri", redirect_uri),
+                ("duration", ar.duration.as_str()),
+                ("scope", &ar.scopes.join(" ")),
+            ],
+        )
+        .map_err(|e| ApiError::Config(format!("Could not build authorization URL: {}", e)))?;
+        Ok(url.into_string())
+    }
+
+    /// Exchanges the code from the authorization code flow for an
+    /// access token, and stores it in the struct.
+    ///
+    /// If the user granted a permanent authorization, the returned refresh
+    /// token is used to get new access tokens when the current one expires.
+    /// The account's whoami info is only loaded if the `identity` scope
+    /// was granted.
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - the code passed to the redirect URI
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// if let Err(err) = api.do_code_login(&code) {
+    ///     panic!("Could not get an access token: {}", err);
+    /// }
+    /// ```
+    pub fn do_code_login(&mut self, code: &str) -> Result<(), ApiError> {
+        debug!("Performing authorization code login");
+        let redirect_uri = self.get_redirect_uri()?;
         let mut form = HashMap::new();
-        form.insert("grant_type", "password");
-        form.insert("username", &self.config.username);
-        form.insert("password", &self.config.password);
+        form.insert("grant_type", "authorization_code".to_owned());
+        form.insert("code", code.to_owned());
+        form.insert("redirect_uri", redirect_uri.to_owned());
+        let data = self.request_access_token(&form)?;
+        let identity = data.has_scope("identity");
+        *self.access_token.lock().unwrap() = Some(data);
+        self.grant = Grant::AuthorizationCode;
+        if identity {
+            self.load_whoami()?;
+        }
+        Ok(())
+    }
+
+    /// Load the account's whoami info into the struct.
+    fn load_whoami(&mut self) -> Result<(), ApiError> {
+        let whoami = self.get_whoami()?;
+        debug!("Returned whoami is {:?}", whoami);
+        self.whoami = Some(whoami);
+        Ok(())
+    }
+
+    /// The redirect URI from the config, required for the
+    /// authorization code flow.
+    fn get_redirect_uri(&self) -> Result<&str, ApiError> {
+        match &self.config.redirect_uri {
+            Some(uri) => Ok(uri),
+            None => Err(ApiError::Config(String::from(
+                "A redirect URI is required for the authorization code flow",
+            ))),
+        }
+    }
+
+    /// Get a new access token from the OAuth endpoint, using the grant
+    /// from the last login, and store it in the struct, replacing any
+    /// existing token.
+    fn refresh_access_token(&self) -> Result<(), ApiError> {
+        let mut token = self.access_token.lock().unwrap();
+        let form = token_form(&self.config, self.grant, token.as_ref())?;
+        let data = self.request_access_token(&form)?;
+        *token = Some(data.keep_refresh_token(token.as_ref()));
+        Ok(())
+    }
+
+    /// Post the form to the OAuth endpoint to get an access token.
+    fn request_access_token(
+        &self,
+        form: &HashMap<&str, String>,
+    ) -> Result<AccessTokenResponse, ApiError> {
+        debug!("Requesting access token with {} grant", form["grant_type"]);
         let mut resp = self
             .client
-            .post(&format!("{}/api/v1/access_token", url))
+            .post(&access_token_url())
             .header("User-Agent", self.config.user_agent.clone())
             .basic_auth(&self.config.client_id, Some(&self.config.client_secret))
-            .form(&form)
+            .form(form)
             .send()?;
-        debug!("Login response code = {}", resp.status().as_str());
-        let data = resp.json::<AccessTokenResponse>()?;
-        debug!("Access token is {}", data.token);
-        self.access_token = Some(data);
-        let whoami = self.get_whoami()?;
-        debug!("Returned whoami is {:?}", whoami);
-        self.whoami = Some(whoami);
+        debug!("Access token response code = {}", resp.status().as_str());
+        let body = resp.text()?;
+        parse_access_token(resp.status(), &body)
+    }
+
+    /// Refresh the access token if there is one and it is
+    /// about to expire.
+    fn refresh_expiring_token(&self) -> Result<(), ApiError> {
+        let expiring = match self.access_token.lock().unwrap().as_ref() {
+            Some(token) => token.is_expiring(),
+            None => false,
+        };
+        if expiring {
+            debug!("Access token is about to expire");
+            self.refresh_access_token()?;
+        }
         Ok(())
     }
 
+    /// Send a request, built by the `build` closure, to the API.
+    ///
+    /// The closure is called again to rebuild the request with new
+    /// headers if the access token had to be refreshed after the API
+    /// responded with a 401.
+    fn send<F>(&self, build: F) -> Result<reqwest::Response, ApiError>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        self.refresh_expiring_token()?;
+        let resp = self.send_with_retries(&build)?;
+        if resp.status() != StatusCode::UNAUTHORIZED || self.access_token.lock().unwrap().is_none()
+        {
+            return Ok(resp);
+        }
+        debug!("Access token rejected, refreshing and retrying");
+        self.refresh_access_token()?;
+        self.send_with_retries(&build)
+    }
+
+    /// Send a request to the API, retrying according to the retry policy
+    /// if it times out or the response status is a transient failure.
+    ///
+    /// The last response is returned if all attempts fail.
+    fn send_with_retries<F>(&self, build: &F) -> Result<reqwest::Response, ApiError>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        let mut attempt = 1;
+        loop {
+            let result = self.send_once(build());
+            let delay = match &result {
+                Ok(resp) if RetryPolicy::is_retryable(resp.status()) => {
+                    self.retry_policy.delay(attempt, Some(resp.headers()))
+                }
+                Err(e) if e.is_timeout() => self.retry_policy.delay(attempt, None),
+                _ => return Ok(result?),
+            };
+            if attempt >= self.retry_policy.max_attempts {
+                return Ok(result?);
+            }
+            debug!("Attempt {} failed, retrying in {:?}", attempt, delay);
+            thread::sleep(delay);
+            attempt += 1;
+        }
+    }
+
+    /// Send a single request to the API, first waiting for the
+    /// rate limit to reset if the request budget has run out.
+    fn send_once(&self, req: reqwest::RequestBuilder) -> reqwest::Result<reqwest::Response> {
+        self.wait_for_rate_limit();
+        debug!("{:?}", req);
+        let resp = req.send()?;
+        self.process_response_headers(resp.headers());
+        Ok(resp)
+    }
+
+    /// Set the policy for retrying requests that fail for transient
+    /// reasons, like server errors. Applies to each request made, including
+    /// each page of a listing.
+    ///
+    /// By default, requests are attempted up to 3 times.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - the [`RetryPolicy`] to use
+    ///
+    /// [`RetryPolicy`]: retry/struct.RetryPolicy.html
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// api.set_retry_policy(RetryPolicy::new(5).max_delay(Duration::from_secs(10)));
+    /// ```
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.retry_policy = policy;
+    }
+
+    /// Block until there's room in the request budget, and take
+    /// a request from it.
+    ///
+    /// The lock isn't held while sleeping, so that the budget can
+    /// still be read; concurrent callers will each wait in turn.
+    fn wait_for_rate_limit(&self) {
+        loop {
+            let wait = {
+                let mut rate_limit = self.rate_limit.lock().unwrap();
+                match rate_limit.as_mut() {
+                    Some(rl) if rl.is_exhausted() => rl.reset_in(),
+                    Some(rl) => {
+                        rl.reserve();
+                        return;
+                    }
+                    None => return,
+                }
+            };
+            debug!("Rate limit exhausted, waiting {:?} for reset", wait);
+            thread::sleep(wait);
+        }
+    }
+
+    /// Returns the state of the request budget, as of the last response.
+    ///
+    /// Requests sent through the struct wait for the budget to reset
+    /// when it runs out, so this is for informational purposes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// if let Some(rl) = api.get_rate_limit() {
+    ///     println!("{} requests left", rl.remaining);
+    /// }
+    /// ```
+    pub fn get_rate_limit(&self) -> Option<RateLimit> {
+        *self.rate_limit.lock().unwrap()
+    }
+
     /// Returns the account's username from the 'api/v1/me' endpoint.
     ///
     /// # Examples
@@ -214,8 +536,7 @@ impl Api {
     /// ```
     pub fn get_whoami(&self) -> Result<Value, ApiError> {
         let mut resp = self.query("GET", "api/v1/me", None, None)?;
-        let data: Value = resp.json()?;
-        Ok(data)
+        parse_json(&mut resp)
     }
 
     /// Returns the username from the stored whoami data.
@@ -236,46 +557,21 @@ impl Api {
     /// Always includes the User Agent header, and includes
     /// the OAuth token if available.
     fn get_headers(&self) -> HeaderMap {
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            USER_AGENT,
-            HeaderValue::from_str(&self.config.user_agent).unwrap(),
-        );
-        if self.access_token.is_some() {
-            let auth_header = HeaderValue::from_str(&format!(
-                "bearer {}",
-                self.access_token.as_ref().unwrap().token
-            ))
-            .unwrap();
-            headers.insert(AUTHORIZATION, auth_header);
-        }
-        headers
+        request_headers(&self.config, self.access_token.lock().unwrap().as_ref())
     }
 
     /// Macros and replacements for the URL path and the
     /// appending to the root OAuth API URL.
-    fn reformat_path(&self, path: &str) -> String {
-        // urls
-        #[cfg(not(test))]
-        let url = "https://oauth.reddit.com";
-        #[cfg(test)]
-        let url = &mockito::server_url();
-
-        let path = if path.contains("{username}") {
-            debug!("Replacing 'username' macro");
-            path.replace("{username}", &self.get_username().unwrap())
-        } else {
-            path.to_owned()
-        };
-        format!("{}/{}", url, path)
+    fn reformat_path(&self, path: &str) -> Result<String, ApiError> {
+        api_url(path, self.get_username())
     }
 
     /// Processing of the response headers.
+    ///
+    /// Stores the rate limit values, if present.
     fn process_response_headers(&self, headers: &HeaderMap) {
-        for header_name in &RATE_LIMIT_HEADER_NAMES {
-            if let Some(value) = headers.get(*header_name) {
-                debug!(">> Header {}: {}", header_name, value.to_str().unwrap());
-            }
+        if let Some(rl) = read_rate_limit_headers(headers) {
+            *self.rate_limit.lock().unwrap() = Some(rl);
         }
     }
 
@@ -284,7 +580,7 @@ impl Api {
     /// # Arguments
     ///
     /// * `method` - A string representing an HTTP method, capable of being parsed by
-    /// [reqwest], i.e. "GET", "POST", etc.
+    ///   [reqwest], i.e. "GET", "POST", etc.
     /// * `path` - A relative URL path (everything after reddit.com/)
     /// * `query` - An optional collection of query parameters
     /// * `form_data` - An optional collection of form data to submit
@@ -318,27 +614,65 @@ impl Api {
         query: Option<Vec<(&str, &str)>>,
         form_data: Option<HashMap<&str, &str>>,
     ) -> Result<reqwest::Response, ApiError> {
-        let method = Method::from_bytes(method.as_bytes()).unwrap();
-        let path = self.reformat_path(path);
-        let req = self
-            .client
-            .request(method, &path)
-            .headers(self.get_headers());
-        let req = match query {
-            Some(q) => req.query(&q),
-            None => req,
-        };
-        debug!("{:?}", req);
-        let resp = match form_data {
-            Some(fd) => req.form(&fd).send()?,
-            None => req.send()?,
-        };
+        let method = Method::from_bytes(method.as_bytes())?;
+        let path = self.reformat_path(path)?;
+        let resp = self.send(|| {
+            let req = self
+                .client
+                .request(method.clone(), &path)
+                .headers(self.get_headers());
+            let req = match &query {
+                Some(q) => req.query(q),
+                None => req,
+            };
+            match &form_data {
+                Some(fd) => req.form(fd),
+                None => req,
+            }
+        })?;
+        self.check_status(resp)
+    }
+
+    /// Post form data to one of the API's `api/*` write endpoints.
+    ///
+    /// Requests a JSON response, and checks its body for the errors that
+    /// Reddit returns alongside a successful status code, like
+    /// "RATELIMIT" or "SUBREDDIT_NOEXIST".
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - A relative URL path (everything after reddit.com/)
+    /// * `form_data` - The form data to submit
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let mut form_data = HashMap::new();
+    /// form_data.insert("thing_id", "t3_aaaaaa");
+    /// form_data.insert("text", "Hello!");
+    /// let data = api.post_json("api/comment", form_data)?;
+    /// ```
+    pub fn post_json(
+        &self,
+        path: &str,
+        mut form_data: HashMap<&str, &str>,
+    ) -> Result<Value, ApiError> {
+        form_data.insert("api_type", "json");
+        let mut resp = self.query("POST", path, None, Some(form_data))?;
+        let data: Value = parse_json(&mut resp)?;
+        check_json_errors(&data)?;
+        Ok(data)
+    }
+
+    /// Turn error responses into the matching error.
+    fn check_status(&self, mut resp: reqwest::Response) -> Result<reqwest::Response, ApiError> {
         let status = resp.status();
-        if status.is_client_error() || status.is_server_error() {
-            return Err(ApiError::from(format!("Error code {}", status.as_str(),)));
+        if !is_error_status(status) {
+            return Ok(resp);
         }
-        self.process_response_headers(&resp.headers());
-        Ok(resp)
+        let reset = retry::parse_retry_after(resp.headers())
+            .or_else(|| self.get_rate_limit().map(|rl| rl.reset_in()));
+        Err(status_error(status, reset, resp.text().unwrap_or_default()))
     }
 
     /// Query the Reddit API via a listing endpoint.
@@ -363,53 +697,74 @@ impl Api {
     /// ```
     pub fn query_listing(&self, ql: QueryListingRequest) -> Result<Vec<Value>, ApiError> {
         debug!("Listing request call: {:?}", ql);
-        let method = Method::GET;
-        let path = self.reformat_path(&ql.path);
-        let headers = self.get_headers();
-
-        let req = self.client.request(method, &path).headers(headers);
         let mut all_resp: Vec<Value> = Vec::new();
-        let mut after = match ql.after {
-            Some(a) => a.to_owned(),
-            None => String::new(),
-        };
-        let mut count = ql.count;
+        let mut paging = ql.paging();
 
         for _ in 0..ql.requests {
-            let req = req.try_clone().unwrap();
-            let req = if ql.params.is_empty() {
-                req.query(ql.params)
-            } else {
-                req
-            };
-            let mut listing_parms = vec![("limit", ql.limit.to_string())];
-            if !after.is_empty() {
-                listing_parms.push(("after", after));
-            }
-            if count > 0 {
-                listing_parms.push(("count", format!("{}", count)));
-            }
-            if ql.show_all {
-                listing_parms.push(("show", "all".to_owned()));
-            }
-            let req = req.query(&listing_parms);
-            let mut resp = req.send()?;
-            if resp.status().is_client_error() || resp.status().is_server_error() {
-                return Err(ApiError::from(format!(
-                    "Error code {}",
-                    resp.status().as_str()
-                )));
-            }
-            let data: Value = resp.json()?;
-            after = data["data"]["after"].as_str().unwrap().to_owned();
-            for item in data["data"]["children"].as_array().unwrap() {
-                count += 1;
-                all_resp.push(item.clone());
+            let (items, next) = self.get_listing_page(&ql, &paging)?;
+            paging.count += items.len() as u64;
+            all_resp.extend(items);
+            match next {
+                Some(next) => paging.cursor = next,
+                None => break,
             }
         }
         Ok(all_resp)
     }
 
+    /// Lazily iterate over the items of a listing endpoint.
+    ///
+    /// Pages of `ql.limit` items are requested as the iterator
+    /// reaches them, continuing until the listing runs out (or,
+    /// when paging backwards with `ql.before`, until it starts), so
+    /// `ql.requests` is ignored. Use `take` and friends to limit
+    /// the number of items.
+    ///
+    /// # Arguments
+    ///
+    /// * `ql` - A [`QueryListingRequest`] struct
+    ///
+    /// [`QueryListingRequest`]: query_listing/struct.QueryListingRequest.html
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let ql = QueryListingRequest::new("r/rust/new", 100, 0);
+    /// for item in api.listing_iter(ql).take(250) {
+    ///     println!("{}", item?["data"]["title"]);
+    /// }
+    /// ```
+    pub fn listing_iter<'a>(&'a self, ql: QueryListingRequest<'a>) -> ListingIter<'a> {
+        debug!("Listing iterator: {:?}", ql);
+        ListingIter::new(self, ql)
+    }
+
+    /// Get a single page of a listing: its items, and the fullname
+    /// to get the next page from, if there is a next page.
+    pub(crate) fn get_listing_page(
+        &self,
+        ql: &QueryListingRequest,
+        paging: &Paging,
+    ) -> Result<(Vec<Value>, Option<String>), ApiError> {
+        let path = self.reformat_path(ql.path)?;
+        let params = ql.query_params(paging);
+        let resp = self.send(|| {
+            self.client
+                .request(Method::GET, &path)
+                .headers(self.get_headers())
+                .query(&params)
+        })?;
+        let data: Value = parse_json(&mut self.check_status(resp)?)?;
+        let items = match data["data"]["children"].as_array() {
+            Some(children) => children.clone(),
+            None => Vec::new(),
+        };
+        let next = data["data"][paging.cursor_param()]
+            .as_str()
+            .map(str::to_owned);
+        Ok((items, next))
+    }
+
     /// Search for subreddits matching the parameter.
     ///
     /// # Arguments
@@ -424,21 +779,21 @@ impl Api {
     ///     Err(err) => panic!(err),
     /// }
     /// ```
-    pub fn search_for_subreddit(&self, name: &str) -> Result<Vec<Subreddit>, ApiError> {
+    pub fn search_for_subreddit(&self, name: &str) -> Result<Vec<Subreddit<'_>>, ApiError> {
         let mut resp = self.query(
             "GET",
             "api/search_reddit_names",
             Some(vec![("query", name), ("exact", "false")]),
             None,
         )?;
-        let data: Value = resp.json()?;
+        let data: Value = parse_json(&mut resp)?;
         Ok(data["names"]
             .as_array()
             .unwrap()
             .iter()
             .filter_map(|v| v.as_str())
             .map(|e| Subreddit {
-                api: &self,
+                api: self,
                 name: e.to_owned(),
             })
             .collect::<Vec<Subreddit>>())
@@ -458,14 +813,14 @@ impl Api {
     ///     Err(err) => panic!(err),
     /// }
     /// ```
-    pub fn get_subreddit(&self, name: &str) -> Result<Subreddit, ApiError> {
+    pub fn get_subreddit(&self, name: &str) -> Result<Subreddit<'_>, ApiError> {
         let matching = self.search_for_subreddit(name)?;
         for sr in matching {
             if sr.name == name {
                 return Ok(sr);
             }
         }
-        Err(ApiError::from(String::from("Subreddit not found")))
+        Err(ApiError::Other(String::from("Subreddit not found")))
     }
 
     /// Get a user by their name.
@@ -484,16 +839,224 @@ impl Api {
     ///     Err(err) => panic!(err),
     /// }
     /// ```
-    pub fn get_user(&self, name: &str) -> Result<User, ApiError> {
+    pub fn get_user(&self, name: &str) -> Result<User<'_>, ApiError> {
         let mut resp = self.query("GET", &format!("user/{}/about", name), None, None)?;
-        let data: Value = resp.json()?;
-        Ok(User {
-            api: self,
-            about: data,
-        })
+        let data: Value = parse_json(&mut resp)?;
+        User::from_about(self, &data)
+    }
+
+    /// Get the logged-in user's inbox.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let unread = api.get_inbox().get_unread(25)?;
+    /// ```
+    pub fn get_inbox(&self) -> Inbox<'_> {
+        Inbox { api: self }
+    }
+
+    /// Get the new modmail of the subreddits that the logged-in user moderates.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let conversations = api.get_modmail().get_conversations(ConversationState::New, 25)?;
+    /// ```
+    pub fn get_modmail(&self) -> Modmail<'_> {
+        Modmail { api: self }
+    }
+
+    /// Get a post by its fullname.
+    ///
+    /// # Arguments
+    ///
+    /// * `fullname` - the post's fullname, i.e. "t3_abc123"
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let post = api.get_post("t3_abc123")?;
+    /// ```
+    pub fn get_post(&self, fullname: &str) -> Result<Post<'_>, ApiError> {
+        let mut resp = self.query("GET", &format!("by_id/{}", fullname), None, None)?;
+        let data: Value = parse_json(&mut resp)?;
+        match data["data"]["children"].get(0) {
+            Some(item) => Post::from_listing_item(self, item),
+            None => Err(ApiError::Other(String::from("Post not found"))),
+        }
+    }
+
+    /// Get a post and the tree of comments on it.
+    ///
+    /// # Arguments
+    ///
+    /// * `article` - the post's id, without the "t3_" prefix
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let tree = api.get_comments("abc123")?;
+    /// println!("{} comments on {}", tree.iter().count(), tree.post.data.title);
+    /// ```
+    pub fn get_comments(&self, article: &str) -> Result<CommentTree<'_>, ApiError> {
+        let mut resp = self.query("GET", &format!("comments/{}", article), None, None)?;
+        let data: Value = parse_json(&mut resp)?;
+        CommentTree::from_response(self, &data)
+    }
+}
+
+/// URL of the OAuth endpoint for getting access tokens.
+fn access_token_url() -> String {
+    // urls
+    #[cfg(not(test))]
+    let url = "https://www.reddit.com";
+    #[cfg(test)]
+    let url = &mockito::server_url();
+
+    format!("{}/api/v1/access_token", url)
+}
+
+/// Macros and replacements for the URL path and the
+/// appending to the root OAuth API URL.
+fn api_url(path: &str, username: Option<String>) -> Result<String, ApiError> {
+    // urls
+    #[cfg(not(test))]
+    let url = "https://oauth.reddit.com";
+    #[cfg(test)]
+    let url = &mockito::server_url();
+
+    let path = if path.contains("{username}") {
+        debug!("Replacing 'username' macro");
+        let username = username.ok_or_else(|| {
+            ApiError::Config(String::from("No username to replace the macro with"))
+        })?;
+        path.replace("{username}", &username)
+    } else {
+        path.to_owned()
+    };
+    Ok(format!("{}/{}", url, path))
+}
+
+/// Generate headers for the request.
+/// Always includes the User Agent header, and includes
+/// the OAuth token if available.
+fn request_headers(config: &Config, token: Option<&AccessTokenResponse>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        USER_AGENT,
+        HeaderValue::from_str(&config.user_agent).unwrap(),
+    );
+    if let Some(token) = token {
+        let auth_header = HeaderValue::from_str(&format!("bearer {}", token.token)).unwrap();
+        headers.insert(AUTHORIZATION, auth_header);
+    }
+    headers
+}
+
+/// Build the form to send to the OAuth endpoint to get a new
+/// access token with the grant.
+fn token_form(
+    config: &Config,
+    grant: Grant,
+    token: Option<&AccessTokenResponse>,
+) -> Result<HashMap<&'static str, String>, ApiError> {
+    let mut form = HashMap::new();
+    match grant {
+        Grant::Password => {
+            form.insert("grant_type", "password".to_owned());
+            form.insert("username", config.username.clone());
+            form.insert("password", config.password.clone());
+        }
+        Grant::AuthorizationCode => {
+            let refresh_token = match token.and_then(|t| t.refresh_token.clone()) {
+                Some(rt) => rt,
+                None => {
+                    return Err(ApiError::Auth(String::from(
+                        "Access token cannot be refreshed without a permanent authorization",
+                    )))
+                }
+            };
+            form.insert("grant_type", "refresh_token".to_owned());
+            form.insert("refresh_token", refresh_token);
+        }
+        Grant::ClientCredentials => {
+            form.insert("grant_type", "client_credentials".to_owned());
+        }
+        Grant::InstalledClient => {
+            form.insert(
+                "grant_type",
+                "https://oauth.reddit.com/grants/installed_client".to_owned(),
+            );
+            form.insert(
+                "device_id",
+                config
+                    .device_id
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_DEVICE_ID.to_owned()),
+            );
+        }
+    }
+    Ok(form)
+}
+
+/// Parse the OAuth endpoint's response, which reports some
+/// failures in the body of an otherwise successful response.
+fn parse_access_token(status: StatusCode, body: &str) -> Result<AccessTokenResponse, ApiError> {
+    if !status.is_success() {
+        return Err(ApiError::Auth(format!(
+            "Access token request failed with code {}",
+            status.as_str()
+        )));
+    }
+    let data: Value = serde_json::from_str(body)?;
+    if let Some(error) = data.get("error") {
+        return Err(ApiError::Auth(format!(
+            "Access token request failed: {}",
+            error
+        )));
+    }
+    let data: AccessTokenResponse = serde_json::from_value(data)?;
+    debug!("Access token is {}", data.token);
+    Ok(data)
+}
+
+/// Log and parse the rate limit headers from a response.
+fn read_rate_limit_headers(headers: &HeaderMap) -> Option<RateLimit> {
+    for header_name in &RATE_LIMIT_HEADER_NAMES {
+        if let Some(value) = headers.get(*header_name) {
+            debug!(">> Header {}: {}", header_name, value.to_str().unwrap());
+        }
+    }
+    RateLimit::from_headers(headers)
+}
+
+/// Whether the response status is an error.
+fn is_error_status(status: StatusCode) -> bool {
+    status.is_client_error() || status.is_server_error()
+}
+
+/// The matching error for an error response.
+fn status_error(status: StatusCode, reset: Option<Duration>, body: String) -> ApiError {
+    match status {
+        StatusCode::TOO_MANY_REQUESTS => ApiError::RateLimited { reset },
+        StatusCode::UNAUTHORIZED => ApiError::Auth(String::from("Access token rejected")),
+        _ => ApiError::Http {
+            status: status.as_u16(),
+            body,
+        },
     }
 }
 
+/// Deserialize the response's body as JSON.
+///
+/// Unlike `reqwest::Response::json`, failures are reported
+/// as `ApiError::Deserialize`.
+fn parse_json<T: DeserializeOwned>(resp: &mut reqwest::Response) -> Result<T, ApiError> {
+    let body = resp.text()?;
+    Ok(serde_json::from_str(&body)?)
+}
+
 /// the program's API access information.
 #[derive(Debug, Deserialize, PartialEq)]
 struct AccessTokenResponse {
@@ -502,15 +1065,60 @@ struct AccessTokenResponse {
     token_type: String,
     expires_in: u64,
     scope: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(skip, default = "Instant::now")]
+    issued_at: Instant,
+}
+
+impl AccessTokenResponse {
+    /// Whether the token has expired, or will expire soon.
+    fn is_expiring(&self) -> bool {
+        self.issued_at.elapsed() + TOKEN_REFRESH_MARGIN >= Duration::from_secs(self.expires_in)
+    }
+
+    /// Carry over the refresh token from the previous token, as
+    /// refreshing doesn't always return a new one.
+    fn keep_refresh_token(mut self, previous: Option<&AccessTokenResponse>) -> Self {
+        if self.refresh_token.is_none() {
+            self.refresh_token = previous.and_then(|t| t.refresh_token.clone());
+        }
+        self
+    }
+
+    /// Whether the token was granted the scope.
+    fn has_scope(&self, scope: &str) -> bool {
+        self.scope
+            .split(&[' ', ','][..])
+            .any(|s| s == scope || s == "*")
+    }
+}
+
+/// The OAuth grant used to get the access token.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Grant {
+    /// The 'script' application username and password grant
+    Password,
+    /// The authorization code flow, refreshed with a refresh token
+    AuthorizationCode,
+    /// The application-only grant for confidential clients
+    ClientCredentials,
+    /// The application-only grant for installed apps
+    InstalledClient,
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{AccessTokenResponse, Api, Config, QueryListingRequest};
-    use mockito::mock;
+    use super::{
+        AccessTokenResponse, Api, ApiError, ApplicationGrant, AuthorizationRequest, Config, Grant,
+        QueryListingRequest, RateLimit, RetryPolicy,
+    };
+    use crate::auth::TokenDuration;
+    use mockito::{mock, Matcher};
+    use std::collections::HashMap;
     use std::fs::File;
     use std::io::Write;
-    use tempfile;
+    use std::time::{Duration, Instant};
 
     fn get_config() -> Config {
         std::default::Default::default()
@@ -528,6 +1136,17 @@ mod tests {
         )
     }
 
+    fn set_access_token(api: &Api, token: &str, expires_in: u64) {
+        *api.access_token.lock().unwrap() = Some(AccessTokenResponse {
+            token: token.to_owned(),
+            token_type: String::from("bearer"),
+            expires_in,
+            scope: String::from("*"),
+            refresh_token: None,
+            issued_at: Instant::now(),
+        });
+    }
+
     #[test]
     fn load_config_from_disk() {
         let original_content = "{\"username\":\"a\",\"password\":\"b\", \
@@ -538,7 +1157,7 @@ mod tests {
         let mut file = File::create(&file_path).unwrap();
         writeln!(file, "{}", original_content).unwrap();
 
-        let config = Config::load_config(&file_path.as_os_str().to_str().unwrap()).unwrap();
+        let config = Config::load_config(file_path.as_os_str().to_str().unwrap()).unwrap();
 
         assert_eq!(config.username, "a");
         assert_eq!(config.password, "b");
@@ -563,7 +1182,7 @@ mod tests {
         let api = get_api();
 
         assert_eq!(api.config, config);
-        assert_eq!(api.access_token, None);
+        assert_eq!(*api.access_token.lock().unwrap(), None);
         assert_eq!(api.whoami, None);
     }
 
@@ -589,6 +1208,378 @@ mod tests {
         _m2.assert();
     }
 
+    #[test]
+    fn access_token_is_expiring() {
+        let api = get_api();
+        set_access_token(&api, "aaaaa", 10000);
+        assert!(!api
+            .access_token
+            .lock()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .is_expiring());
+
+        set_access_token(&api, "aaaaa", 30);
+        assert!(api
+            .access_token
+            .lock()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .is_expiring());
+    }
+
+    #[test]
+    fn query_refreshes_expiring_token() {
+        let _m1 = mock("POST", "/api/v1/access_token")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(get_sample_atr())
+            .create();
+        let _m2 = mock("GET", "/some/endpoint")
+            .match_header("authorization", "bearer aaaaa")
+            .with_status(200)
+            .create();
+
+        let api = get_api();
+        set_access_token(&api, "stale", 30);
+        api.query("GET", "some/endpoint", None, None).unwrap();
+
+        _m1.assert();
+        _m2.assert();
+    }
+
+    #[test]
+    fn query_retries_on_unauthorized() {
+        let _m1 = mock("POST", "/api/v1/access_token")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(get_sample_atr())
+            .create();
+        let _m2 = mock("GET", "/some/endpoint")
+            .match_header("authorization", "bearer rejected")
+            .with_status(401)
+            .create();
+        let _m3 = mock("GET", "/some/endpoint")
+            .match_header("authorization", "bearer aaaaa")
+            .with_status(200)
+            .create();
+
+        let api = get_api();
+        set_access_token(&api, "rejected", 10000);
+        api.query("GET", "some/endpoint", None, None).unwrap();
+
+        _m1.assert();
+        _m2.assert();
+        _m3.assert();
+    }
+
+    #[test]
+    fn do_app_login_client_credentials() {
+        let _m1 = mock("POST", "/api/v1/access_token")
+            .match_body("grant_type=client_credentials")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(get_sample_atr())
+            .create();
+        let _m2 = mock("GET", "/some/endpoint")
+            .match_header("authorization", "bearer aaaaa")
+            .with_status(200)
+            .create();
+
+        let mut api = get_api();
+        api.do_app_login(ApplicationGrant::ClientCredentials)
+            .unwrap();
+        api.query("GET", "some/endpoint", None, None).unwrap();
+
+        assert_eq!(api.grant, Grant::ClientCredentials);
+        assert_eq!(api.whoami, None);
+        _m1.assert();
+        _m2.assert();
+    }
+
+    #[test]
+    fn do_app_login_installed_client() {
+        let _m1 = mock("POST", "/api/v1/access_token")
+            .match_body(Matcher::Regex("device_id=abcdefghijklmnopqrst".to_owned()))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(get_sample_atr())
+            .create();
+
+        let config = Config {
+            device_id: Some(String::from("abcdefghijklmnopqrst")),
+            ..get_config()
+        };
+        let mut api = Api::new(config);
+        api.do_app_login(ApplicationGrant::InstalledClient).unwrap();
+
+        assert_eq!(api.grant, Grant::InstalledClient);
+        _m1.assert();
+    }
+
+    #[test]
+    fn username_macro_without_whoami() {
+        assert!(get_api()
+            .query("GET", "user/{username}/about", None, None)
+            .is_err());
+    }
+
+    #[test]
+    fn get_authorization_url() {
+        let config = Config {
+            client_id: String::from("abc"),
+            redirect_uri: Some(String::from("http://localhost:8080/callback")),
+            ..get_config()
+        };
+        let api = Api::new(config);
+        let scopes = ["identity", "read"];
+        let ar = AuthorizationRequest::new(&scopes, "xyz").duration(TokenDuration::Permanent);
+        let url = api.get_authorization_url(ar).unwrap();
+
+        assert_eq!(
+            url,
+            "https://www.reddit.com/api/v1/authorize?client_id=abc&response_type=code\
+             &state=xyz&redirect_uri=http%3A%2F%2Flocalhost%3A8080%2Fcallback\
+             &duration=permanent&scope=identity+read"
+        );
+    }
+
+    #[test]
+    fn get_authorization_url_no_redirect_uri() {
+        let ar = AuthorizationRequest::new(&[], "xyz");
+
+        assert!(get_api().get_authorization_url(ar).is_err());
+    }
+
+    #[test]
+    fn do_code_login() {
+        let _m1 = mock("POST", "/api/v1/access_token")
+            .match_body(Matcher::Regex("grant_type=authorization_code".to_owned()))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                "{\"access_token\":\"aaaaa\",\"token_type\":\"bearer\", \
+                 \"expires_in\":3600,\"scope\":\"identity read\",\"refresh_token\":\"rrrrr\"}",
+            )
+            .create();
+        let _m2 = mock("GET", "/api/v1/me")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body("{\"name\":\"test-name\"}")
+            .create();
+
+        let config = Config {
+            redirect_uri: Some(String::from("http://localhost:8080/callback")),
+            ..get_config()
+        };
+        let mut api = Api::new(config);
+        api.do_code_login("ccccc").unwrap();
+        let token = api.access_token.lock().unwrap().take().unwrap();
+
+        assert_eq!(api.grant, Grant::AuthorizationCode);
+        assert_eq!(token.token, "aaaaa");
+        assert_eq!(token.refresh_token, Some(String::from("rrrrr")));
+        assert_eq!(api.get_username().unwrap(), "test-name");
+        _m1.assert();
+        _m2.assert();
+    }
+
+    #[test]
+    fn refresh_with_refresh_token() {
+        let _m1 = mock("POST", "/api/v1/access_token")
+            .match_body(Matcher::Regex("(^|&)refresh_token=rrrrr".to_owned()))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(get_sample_atr())
+            .create();
+
+        let mut api = get_api();
+        api.grant = Grant::AuthorizationCode;
+        set_access_token(&api, "stale", 30);
+        api.access_token
+            .lock()
+            .unwrap()
+            .as_mut()
+            .unwrap()
+            .refresh_token = Some(String::from("rrrrr"));
+        api.refresh_access_token().unwrap();
+        let token = api.access_token.lock().unwrap().take().unwrap();
+
+        assert_eq!(token.token, "aaaaa");
+        assert_eq!(token.refresh_token, Some(String::from("rrrrr")));
+        _m1.assert();
+    }
+
+    #[test]
+    fn refresh_without_refresh_token() {
+        let mut api = get_api();
+        api.grant = Grant::AuthorizationCode;
+        set_access_token(&api, "stale", 30);
+
+        assert!(api.refresh_access_token().is_err());
+    }
+
+    #[test]
+    fn query_tracks_rate_limit() {
+        let _m1 = mock("GET", "/some/endpoint")
+            .with_status(200)
+            .with_header("X-Ratelimit-Used", "10")
+            .with_header("X-Ratelimit-Remaining", "590.0")
+            .with_header("X-Ratelimit-Reset", "120")
+            .create();
+
+        let api = get_api();
+        assert_eq!(api.get_rate_limit(), None);
+        api.query("GET", "some/endpoint", None, None).unwrap();
+        let rl = api.get_rate_limit().unwrap();
+
+        assert_eq!(rl.used, 10);
+        assert_eq!(rl.remaining, 590.0);
+        assert!(rl.reset_in() > Duration::from_secs(110));
+        _m1.assert();
+    }
+
+    #[test]
+    fn query_waits_for_rate_limit_reset() {
+        let _m1 = mock("GET", "/some/endpoint").with_status(200).create();
+
+        let api = get_api();
+        *api.rate_limit.lock().unwrap() = Some(RateLimit {
+            used: 600,
+            remaining: 0.0,
+            reset: Instant::now() + Duration::from_millis(200),
+        });
+        let start = Instant::now();
+        api.query("GET", "some/endpoint", None, None).unwrap();
+
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        _m1.assert();
+    }
+
+    #[test]
+    fn query_retries_transient_failures() {
+        let _m1 = mock("GET", "/some/endpoint")
+            .with_status(503)
+            .expect(3)
+            .create();
+
+        let mut api = get_api();
+        api.set_retry_policy(RetryPolicy::new(3).base_delay(Duration::from_millis(1)));
+        assert!(api.query("GET", "some/endpoint", None, None).is_err());
+
+        _m1.assert();
+    }
+
+    #[test]
+    fn query_does_not_retry_client_errors() {
+        let _m1 = mock("GET", "/some/endpoint")
+            .with_status(404)
+            .with_body("not here")
+            .expect(1)
+            .create();
+
+        let api = get_api();
+        match api.query("GET", "some/endpoint", None, None) {
+            Err(ApiError::Http { status, body }) => {
+                assert_eq!(status, 404);
+                assert_eq!(body, "not here");
+            }
+            _ => panic!("Expected HTTP error"),
+        }
+
+        _m1.assert();
+    }
+
+    #[test]
+    fn query_rate_limited() {
+        let _m1 = mock("GET", "/some/endpoint")
+            .with_status(429)
+            .with_header("Retry-After", "30")
+            .create();
+
+        let mut api = get_api();
+        api.set_retry_policy(RetryPolicy::none());
+        match api.query("GET", "some/endpoint", None, None) {
+            Err(ApiError::RateLimited { reset }) => {
+                assert_eq!(reset, Some(Duration::from_secs(30)))
+            }
+            _ => panic!("Expected rate limited error"),
+        }
+
+        _m1.assert();
+    }
+
+    #[test]
+    fn do_login_bad_credentials() {
+        let _m1 = mock("POST", "/api/v1/access_token")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body("{\"error\":\"invalid_grant\"}")
+            .create();
+
+        let mut api = get_api();
+        match api.do_login() {
+            Err(ApiError::Auth(_)) => (),
+            _ => panic!("Expected auth error"),
+        }
+
+        _m1.assert();
+    }
+
+    #[test]
+    fn query_listing_retries_transient_failures() {
+        let _m1 = mock("GET", "/some/endpoint?limit=3&show=all")
+            .with_status(502)
+            .expect(2)
+            .create();
+
+        let mut api = get_api();
+        api.set_retry_policy(RetryPolicy::new(2).base_delay(Duration::from_millis(1)));
+        let ql = QueryListingRequest::new("some/endpoint", 3, 1);
+        assert!(api.query_listing(ql).is_err());
+
+        _m1.assert();
+    }
+
+    #[test]
+    fn post_json() {
+        let _m1 = mock("POST", "/api/comment")
+            .match_body(Matcher::Regex("api_type=json".to_owned()))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body("{\"json\":{\"errors\":[],\"data\":{\"things\":[]}}}")
+            .create();
+
+        let mut form = HashMap::new();
+        form.insert("thing_id", "t3_aaaaa");
+        let data = get_api().post_json("api/comment", form).unwrap();
+
+        assert!(data["json"]["data"]["things"].is_array());
+        _m1.assert();
+    }
+
+    #[test]
+    fn post_json_errors() {
+        let _m1 = mock("POST", "/api/comment")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                "{\"json\":{\"errors\":[[\"RATELIMIT\", \
+                 \"you are doing that too much. try again in 5 minutes.\",\"ratelimit\"]]}}",
+            )
+            .create();
+
+        match get_api().post_json("api/comment", HashMap::new()) {
+            Err(ApiError::RateLimited { reset }) => {
+                assert_eq!(reset, Some(Duration::from_secs(300)))
+            }
+            _ => panic!("Expected rate limited error"),
+        }
+        _m1.assert();
+    }
+
     #[test]
     fn query_listing() {
         let body = "{\"data\":{\"kind\":\"Listing\",\"after\":\"t3_ccccc\",\"children\": \
@@ -606,6 +1597,125 @@ mod tests {
         _m1.assert();
     }
 
+    #[test]
+    fn query_listing_stops_at_last_page() {
+        let body = "{\"data\":{\"kind\":\"Listing\",\"after\":null,\"children\": \
+                    [{\"data\":{\"id\":\"aaaaa\"},\"kind\":\"t3\"}]}}";
+        let _m1 = mock("GET", "/some/last?limit=2&show=all")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .expect(1)
+            .create();
+        let ql = QueryListingRequest::new("some/last", 2, 3);
+        let values = get_api().query_listing(ql).unwrap();
+
+        assert_eq!(values.len(), 1);
+        _m1.assert();
+    }
+
+    #[test]
+    fn listing_iter() {
+        let page = |after: &str, ids: &[&str]| {
+            let children: Vec<String> = ids
+                .iter()
+                .map(|id| format!("{{\"data\":{{\"id\":\"{}\"}},\"kind\":\"t3\"}}", id))
+                .collect();
+            format!(
+                "{{\"data\":{{\"kind\":\"Listing\",\"after\":{},\"children\":[{}]}}}}",
+                after,
+                children.join(",")
+            )
+        };
+        let _m1 = mock("GET", "/some/pages?limit=2&show=all")
+            .with_status(200)
+            .with_body(page("\"t3_bbbbb\"", &["aaaaa", "bbbbb"]))
+            .create();
+        let _m2 = mock("GET", "/some/pages?limit=2&after=t3_bbbbb&count=2&show=all")
+            .with_status(200)
+            .with_body(page("null", &["ccccc"]))
+            .create();
+        let api = get_api();
+
+        let ids: Vec<String> = api
+            .listing_iter(QueryListingRequest::new("some/pages", 2, 0))
+            .map(|item| item.unwrap()["data"]["id"].as_str().unwrap().to_owned())
+            .collect();
+        assert_eq!(ids, vec!["aaaaa", "bbbbb", "ccccc"]);
+        _m1.assert();
+        _m2.assert();
+
+        // only fetches the pages that are needed
+        let _m3 = mock("GET", "/some/lazy?limit=2&show=all")
+            .with_status(200)
+            .with_body(page("\"t3_bbbbb\"", &["aaaaa", "bbbbb"]))
+            .expect(1)
+            .create();
+        let _m4 = mock("GET", "/some/lazy?limit=2&after=t3_bbbbb&count=2&show=all")
+            .with_status(200)
+            .with_body(page("null", &["ccccc"]))
+            .expect(0)
+            .create();
+        let taken = api
+            .listing_iter(QueryListingRequest::new("some/lazy", 2, 0))
+            .take(2)
+            .count();
+        assert_eq!(taken, 2);
+        _m3.assert();
+        _m4.assert();
+    }
+
+    #[test]
+    fn query_listing_before() {
+        let _m1 = mock(
+            "GET",
+            "/some/back?sort=new&limit=1&before=t3_ccccc&raw_json=1",
+        )
+        .with_status(200)
+        .with_body(
+            "{\"data\":{\"before\":\"t3_bbbbb\",\"after\":\"t3_bbbbb\",\"children\": \
+                 [{\"data\":{\"id\":\"bbbbb\"},\"kind\":\"t3\"}]}}",
+        )
+        .create();
+        let _m2 = mock(
+            "GET",
+            "/some/back?sort=new&limit=1&before=t3_bbbbb&count=1&raw_json=1",
+        )
+        .with_status(200)
+        .with_body(
+            "{\"data\":{\"before\":null,\"after\":\"t3_aaaaa\",\"children\": \
+                 [{\"data\":{\"id\":\"aaaaa\"},\"kind\":\"t3\"}]}}",
+        )
+        .create();
+        let ql = QueryListingRequest::new("some/back", 1, 5)
+            .params(&[("sort", "new"), ("limit", "100")])
+            .before(Some("t3_ccccc"))
+            .show_all(false)
+            .raw_json(true);
+        let values = get_api().query_listing(ql).unwrap();
+
+        assert_eq!(values.len(), 2);
+        _m1.assert();
+        _m2.assert();
+    }
+
+    #[test]
+    fn listing_iter_error() {
+        let _m1 = mock("GET", "/some/missing?limit=2&show=all")
+            .with_status(404)
+            .expect(1)
+            .create();
+        let api = get_api();
+        let mut iter = api.listing_iter(QueryListingRequest::new("some/missing", 2, 0));
+
+        match iter.next() {
+            Some(Err(ApiError::Http { status: 404, .. })) => (),
+            _ => panic!("Expected HTTP error"),
+        }
+        assert!(iter.next().is_none());
+        _m1.assert();
+    }
+
     #[test]
     fn search_for_subreddit() {
         let body = "{\"names\":[\"rust1\",\"rust2\",\"rust3\"]}";
@@ -0,0 +1,86 @@
+//! Write operations shared by posts and comments.
+//!
+//! Both are "things" to the API, identified by their fullnames,
+//! so the endpoints that act on them are the same.
+
+use super::post::Post;
+use super::VoteDirection;
+use crate::{Api, ApiError};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Call an endpoint that only takes the thing's fullname as `id`.
+pub(crate) fn act(api: &Api, path: &str, fullname: &str) -> Result<(), ApiError> {
+    let mut form_data = HashMap::new();
+    form_data.insert("id", fullname);
+    api.post_json(path, form_data)?;
+    Ok(())
+}
+
+/// Get the single thing returned by endpoints that create or update one.
+pub(crate) fn first_thing(data: &Value) -> Result<Value, ApiError> {
+    match data["json"]["data"]["things"].get(0) {
+        Some(thing) => Ok(thing.clone()),
+        None => Err(ApiError::Other(String::from("No item in response"))),
+    }
+}
+
+/// Reply to the thing, returning the new comment's listing item.
+pub(crate) fn reply(api: &Api, fullname: &str, text: &str) -> Result<Value, ApiError> {
+    let mut form_data = HashMap::new();
+    form_data.insert("thing_id", fullname);
+    form_data.insert("text", text);
+    first_thing(&api.post_json("api/comment", form_data)?)
+}
+
+/// Replace the thing's text, returning its updated listing item.
+pub(crate) fn edit(api: &Api, fullname: &str, text: &str) -> Result<Value, ApiError> {
+    let mut form_data = HashMap::new();
+    form_data.insert("thing_id", fullname);
+    form_data.insert("text", text);
+    first_thing(&api.post_json("api/editusertext", form_data)?)
+}
+
+/// Vote on the thing.
+pub(crate) fn vote(api: &Api, fullname: &str, direction: VoteDirection) -> Result<(), ApiError> {
+    let mut form_data = HashMap::new();
+    form_data.insert("id", fullname);
+    form_data.insert("dir", direction.as_str());
+    api.post_json("api/vote", form_data)?;
+    Ok(())
+}
+
+/// Save the thing, optionally to a category.
+pub(crate) fn save(api: &Api, fullname: &str, category: Option<&str>) -> Result<(), ApiError> {
+    let mut form_data = HashMap::new();
+    form_data.insert("id", fullname);
+    if let Some(category) = category {
+        form_data.insert("category", category);
+    }
+    api.post_json("api/save", form_data)?;
+    Ok(())
+}
+
+/// Report the thing to the subreddit's moderators.
+pub(crate) fn report(api: &Api, fullname: &str, reason: &str) -> Result<(), ApiError> {
+    let mut form_data = HashMap::new();
+    form_data.insert("thing_id", fullname);
+    form_data.insert("reason", reason);
+    api.post_json("api/report", form_data)?;
+    Ok(())
+}
+
+/// Submit a new post, returning it once created.
+///
+/// The submit endpoint only responds with the new post's fullname
+/// and URL, so the post is then fetched by its fullname.
+pub(crate) fn submit<'a>(
+    api: &'a Api,
+    form_data: HashMap<&str, &str>,
+) -> Result<Post<'a>, ApiError> {
+    let data = api.post_json("api/submit", form_data)?;
+    match data["json"]["data"]["name"].as_str() {
+        Some(fullname) => api.get_post(fullname),
+        None => Err(ApiError::Other(String::from("No post in response"))),
+    }
+}
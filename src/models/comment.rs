@@ -1,26 +1,913 @@
 //! Struct-based access to various comment APIs.
 //!
-//! Get a comment struct with:
+//! Get a post's comment tree with:
 //!
-//! TODO
+//! ```rust,no_run,ignore
+//! let tree = api.get_comments("abc123")?;
+//! for comment in tree.iter() {
+//!     println!("{}{}", " ".repeat(comment.data.depth as usize), comment.data.body);
+//! }
+//! ```
 
-use super::user::User;
-use crate::Api;
+use super::actions;
+use super::moderation::ModerationData;
+use super::post::Post;
+use super::{from_thing, ApiClient, ApiHandle, VoteDirection};
+use crate::errors::check_json_errors;
+use crate::{parse_json, Api, ApiError};
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::slice;
 
-/// Maps to a single comment.
-#[derive(Clone)]
-pub struct Comment<'a> {
+/// The kind of comment items in listings.
+pub const KIND: &str = "t1";
+/// The kind of "load more comments" items in listings.
+pub const MORE_KIND: &str = "more";
+/// The most comment ids that `api/morechildren` accepts per request.
+pub const MORE_CHILDREN_BATCH: usize = 100;
+
+/// A comment's data, as returned by the API for `t1` items.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct CommentData {
+    /// The comment's id, i.e. "def456"
+    pub id: String,
+    /// The comment's fullname, i.e. "t1_def456"
+    #[serde(rename = "name")]
+    pub fullname: String,
+    /// The fullname of the comment's parent, either a post or a comment
+    pub parent_id: String,
+    /// The fullname of the post the comment was made on
+    pub link_id: String,
+    /// The comment author's username
+    pub author: String,
+    /// The comment's markdown text
+    pub body: String,
+    /// The comment's text rendered as HTML
+    #[serde(default)]
+    pub body_html: String,
+    /// The comment's score
+    #[serde(default)]
+    pub score: i64,
+    /// How deep in the tree the comment is, 0 for top-level comments
+    #[serde(default)]
+    pub depth: u32,
+    /// When the comment was made, in seconds since the epoch
+    pub created_utc: f64,
+    /// When the comment was last edited, in seconds since the epoch
+    #[serde(default, deserialize_with = "edited_timestamp")]
+    pub edited: Option<f64>,
+    /// How the comment is distinguished, i.e. "moderator"
+    #[serde(default)]
+    pub distinguished: Option<String>,
+    /// Whether the comment is stickied on its post
+    #[serde(default)]
+    pub stickied: bool,
+    /// The comment's moderation state
+    #[serde(flatten)]
+    pub moderation: ModerationData,
+}
+
+/// A placeholder for comments that were left out of a
+/// response, from a `more` item.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct MoreComments {
+    /// The placeholder's id
+    pub id: String,
+    /// The fullname of the comments' parent
+    pub parent_id: String,
+    /// The total number of comments left out, including replies
+    #[serde(default)]
+    pub count: u64,
+    /// How deep in the tree the comments are
+    #[serde(default)]
+    pub depth: u32,
+    /// The ids of the comments left out
+    #[serde(default)]
+    pub children: Vec<String>,
+}
+
+/// How a moderator's or admin's comment is marked.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Distinguish {
+    /// Marked as coming from a moderator.
+    Moderator,
+    /// Marked as coming from an admin.
+    Admin,
+    /// Marked with the special distinguish.
+    Special,
+    /// Not marked.
+    None,
+}
+
+impl Distinguish {
+    /// The value to pass to the distinguish endpoint.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Distinguish::Moderator => "yes",
+            Distinguish::Admin => "admin",
+            Distinguish::Special => "special",
+            Distinguish::None => "no",
+        }
+    }
+}
+
+/// Reddit sends `false` for comments that weren't edited,
+/// and a timestamp for those that were.
+fn edited_timestamp<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Value::deserialize(deserializer)?.as_f64())
+}
+
+/// Maps to a single comment and its replies.
+///
+/// The `A` type parameter is the API client that the struct
+/// was retrieved from, either `Api` or `AsyncApi`.
+pub struct Comment<'a, A: ApiClient<'a> = Api> {
     /// Rerefence to the source `Api` struct. Used for calling API endpoints.
-    pub api: &'a Api,
-    /// Comment's user.
-    pub user: User<'a>,
-    /// The Comment's link, if a link-type Comment.
-    pub link: Option<String>,
-    /// The Comment's text, if a text-type Comment.
-    pub text: Option<String>,
+    pub api: A::Handle,
+    /// Comment's data.
+    pub data: CommentData,
+    /// Comment's replies.
+    pub replies: Vec<CommentNode<'a, A>>,
+}
+
+impl<'a, A: ApiClient<'a>> Clone for Comment<'a, A> {
+    fn clone(&self) -> Self {
+        Comment {
+            api: self.api.clone(),
+            data: self.data.clone(),
+            replies: self.replies.clone(),
+        }
+    }
+}
+
+impl<'a, A: ApiClient<'a>> Comment<'a, A> {
+    /// Create a comment struct, along with its replies,
+    /// from a `t1` item in a listing.
+    ///
+    /// # Arguments
+    ///
+    /// * `api` - the API client
+    /// * `item` - the listing item
+    pub fn from_listing_item<H>(api: H, item: &Value) -> Result<Self, ApiError>
+    where
+        H: ApiHandle<'a, Client = A>,
+        A: ApiClient<'a, Handle = H>,
+    {
+        // "replies" is an empty string for comments without any
+        let replies = match &item["data"]["replies"] {
+            Value::Object(_) => parse_nodes(api.clone(), &item["data"]["replies"])?,
+            _ => Vec::new(),
+        };
+        Ok(Comment {
+            api,
+            data: from_thing(item, KIND)?,
+            replies,
+        })
+    }
+
+    /// Get the comment's fullname.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let fullname = comment.fullname();
+    /// ```
+    pub fn fullname(&self) -> &str {
+        &self.data.fullname
+    }
+}
+
+impl<'a> Comment<'a> {
+    /// Reply to the comment.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - the reply's markdown text
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let reply = comment.reply("Hello!")?;
+    /// ```
+    pub fn reply(&self, text: &str) -> Result<Comment<'a>, ApiError> {
+        let item = actions::reply(self.api, self.fullname(), text)?;
+        Comment::from_listing_item(self.api, &item)
+    }
+
+    /// Replace the comment's text.
+    ///
+    /// The returned comment has no replies, as they are
+    /// not included in the response.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - the comment's new markdown text
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let comment = comment.edit("Updated text")?;
+    /// ```
+    pub fn edit(&self, text: &str) -> Result<Comment<'a>, ApiError> {
+        let item = actions::edit(self.api, self.fullname(), text)?;
+        Comment::from_listing_item(self.api, &item)
+    }
+
+    /// Delete the comment.
+    pub fn delete(&self) -> Result<(), ApiError> {
+        actions::act(self.api, "api/del", self.fullname())
+    }
+
+    /// Vote on the comment.
+    ///
+    /// # Arguments
+    ///
+    /// * `direction` - the direction to vote in
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// comment.vote(VoteDirection::Up)?;
+    /// ```
+    pub fn vote(&self, direction: VoteDirection) -> Result<(), ApiError> {
+        actions::vote(self.api, self.fullname(), direction)
+    }
+
+    /// Save the comment, optionally to a category.
+    ///
+    /// # Arguments
+    ///
+    /// * `category` - the category to save the comment to (Reddit Gold only)
+    pub fn save(&self, category: Option<&str>) -> Result<(), ApiError> {
+        actions::save(self.api, self.fullname(), category)
+    }
+
+    /// Unsave the comment.
+    pub fn unsave(&self) -> Result<(), ApiError> {
+        actions::act(self.api, "api/unsave", self.fullname())
+    }
+
+    /// Report the comment to the subreddit's moderators.
+    ///
+    /// # Arguments
+    ///
+    /// * `reason` - why the comment is being reported
+    pub fn report(&self, reason: &str) -> Result<(), ApiError> {
+        actions::report(self.api, self.fullname(), reason)
+    }
+
+    /// Distinguish the comment, optionally stickying it to the
+    /// top of the post. Only top-level comments can be stickied.
+    ///
+    /// # Arguments
+    ///
+    /// * `how` - how to mark the comment
+    /// * `sticky` - whether to sticky the comment
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let comment = comment.distinguish(Distinguish::Moderator, true)?;
+    /// ```
+    pub fn distinguish(&self, how: Distinguish, sticky: bool) -> Result<Comment<'a>, ApiError> {
+        let mut form_data = HashMap::new();
+        form_data.insert("id", self.fullname());
+        form_data.insert("how", how.as_str());
+        form_data.insert("sticky", if sticky { "true" } else { "false" });
+        let item = actions::first_thing(&self.api.post_json("api/distinguish", form_data)?)?;
+        Comment::from_listing_item(self.api, &item)
+    }
+
+    /// Distinguish the comment as a moderator and sticky it.
+    pub fn sticky(&self) -> Result<Comment<'a>, ApiError> {
+        self.distinguish(Distinguish::Moderator, true)
+    }
+
+    /// Remove the comment's distinguish, unstickying it.
+    pub fn undistinguish(&self) -> Result<Comment<'a>, ApiError> {
+        self.distinguish(Distinguish::None, false)
+    }
+}
+
+/// A single item in a comment tree.
+// almost every node is a comment, so boxing them would only add allocations
+#[allow(clippy::large_enum_variant)]
+pub enum CommentNode<'a, A: ApiClient<'a> = Api> {
+    /// A comment, with its replies
+    Comment(Comment<'a, A>),
+    /// A placeholder for comments that weren't included
+    More(MoreComments),
 }
 
-impl<'a> Comment<'a> {}
+impl<'a, A: ApiClient<'a>> Clone for CommentNode<'a, A> {
+    fn clone(&self) -> Self {
+        match self {
+            CommentNode::Comment(c) => CommentNode::Comment(c.clone()),
+            CommentNode::More(m) => CommentNode::More(m.clone()),
+        }
+    }
+}
+
+impl<'a, A: ApiClient<'a>> CommentNode<'a, A> {
+    /// Create a node from a `t1` or `more` item in a listing.
+    ///
+    /// # Arguments
+    ///
+    /// * `api` - the API client
+    /// * `item` - the listing item
+    pub fn from_listing_item<H>(api: H, item: &Value) -> Result<Self, ApiError>
+    where
+        H: ApiHandle<'a, Client = A>,
+        A: ApiClient<'a, Handle = H>,
+    {
+        if item["kind"] == MORE_KIND {
+            Ok(CommentNode::More(from_thing(item, MORE_KIND)?))
+        } else {
+            Ok(CommentNode::Comment(Comment::from_listing_item(api, item)?))
+        }
+    }
+
+    /// Get the fullname of the node's parent.
+    pub fn parent_id(&self) -> &str {
+        match self {
+            CommentNode::Comment(c) => &c.data.parent_id,
+            CommentNode::More(m) => &m.parent_id,
+        }
+    }
+
+    /// Get the node's comment, if it isn't a placeholder.
+    pub fn as_comment(&self) -> Option<&Comment<'a, A>> {
+        match self {
+            CommentNode::Comment(c) => Some(c),
+            CommentNode::More(_) => None,
+        }
+    }
+}
+
+/// Parse the children of a listing of comments.
+fn parse_nodes<'a, A: ApiClient<'a>>(
+    api: A::Handle,
+    listing: &Value,
+) -> Result<Vec<CommentNode<'a, A>>, ApiError> {
+    match listing["data"]["children"].as_array() {
+        Some(children) => children
+            .iter()
+            .map(|item| CommentNode::from_listing_item(api.clone(), item))
+            .collect(),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Arrange a flat list of nodes into trees under the parent.
+///
+/// Nodes are kept in the order they were given, which for
+/// `api/morechildren` is the order they should be displayed in.
+fn assemble<'a, A: ApiClient<'a>>(
+    parent_id: &str,
+    nodes: Vec<CommentNode<'a, A>>,
+) -> Vec<CommentNode<'a, A>> {
+    let mut by_parent: HashMap<String, Vec<CommentNode<'a, A>>> = HashMap::new();
+    for node in nodes {
+        by_parent
+            .entry(node.parent_id().to_owned())
+            .or_default()
+            .push(node);
+    }
+    attach(parent_id, &mut by_parent)
+}
+
+/// Take the nodes under the parent, recursively attaching their replies.
+fn attach<'a, A: ApiClient<'a>>(
+    parent_id: &str,
+    by_parent: &mut HashMap<String, Vec<CommentNode<'a, A>>>,
+) -> Vec<CommentNode<'a, A>> {
+    let mut nodes = by_parent.remove(parent_id).unwrap_or_default();
+    for node in nodes.iter_mut() {
+        if let CommentNode::Comment(c) = node {
+            let replies = attach(&c.data.fullname, by_parent);
+            c.replies.extend(replies);
+        }
+    }
+    nodes
+}
+
+/// Replace the first expandable placeholder in the tree, depth-first,
+/// with the nodes fetched for it. Returns whether one was found.
+///
+/// Placeholders whose children were already `requested` are skipped,
+/// as the API returns the same placeholder again for comments that it
+/// can't load, like deleted ones.
+fn expand_first<'a, A: ApiClient<'a>, F>(
+    nodes: &mut Vec<CommentNode<'a, A>>,
+    fetch: &mut F,
+    requested: &mut HashSet<Vec<String>>,
+) -> Result<bool, ApiError>
+where
+    F: FnMut(&MoreComments) -> Result<Vec<CommentNode<'a, A>>, ApiError>,
+{
+    for i in 0..nodes.len() {
+        let expanded = match &mut nodes[i] {
+            // "continue this thread" links have no children to fetch
+            CommentNode::More(more)
+                if !more.children.is_empty() && !requested.contains(&more.children) =>
+            {
+                requested.insert(more.children.clone());
+                fetch(more)?
+            }
+            CommentNode::Comment(c) => {
+                if expand_first(&mut c.replies, fetch, requested)? {
+                    return Ok(true);
+                }
+                continue;
+            }
+            CommentNode::More(_) => continue,
+        };
+        nodes.splice(i..=i, expanded);
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+/// Fetch the comments left out of a thread, in batches.
+fn fetch_more<'a>(
+    api: &'a Api,
+    link_id: &str,
+    more: &MoreComments,
+) -> Result<Vec<CommentNode<'a>>, ApiError> {
+    let mut nodes = Vec::new();
+    for batch in more.children.chunks(MORE_CHILDREN_BATCH) {
+        let children = batch.join(",");
+        let query = vec![
+            ("api_type", "json"),
+            ("link_id", link_id),
+            ("children", children.as_str()),
+            ("limit_children", "false"),
+        ];
+        let mut resp = api.query("GET", "api/morechildren", Some(query), None)?;
+        let data: Value = parse_json(&mut resp)?;
+        check_json_errors(&data)?;
+        if let Some(things) = data["json"]["data"]["things"].as_array() {
+            for item in things {
+                nodes.push(CommentNode::from_listing_item(api, item)?);
+            }
+        }
+    }
+    Ok(assemble(&more.parent_id, nodes))
+}
+
+/// A post and the tree of comments on it.
+///
+/// The `A` type parameter is the API client that the struct
+/// was retrieved from, either `Api` or `AsyncApi`.
+pub struct CommentTree<'a, A: ApiClient<'a> = Api> {
+    /// The post that the comments were made on.
+    pub post: Post<'a, A>,
+    /// The top-level comments, with their replies.
+    pub comments: Vec<CommentNode<'a, A>>,
+}
+
+impl<'a, A: ApiClient<'a>> Clone for CommentTree<'a, A> {
+    fn clone(&self) -> Self {
+        CommentTree {
+            post: self.post.clone(),
+            comments: self.comments.clone(),
+        }
+    }
+}
+
+impl<'a, A: ApiClient<'a>> CommentTree<'a, A> {
+    /// Create a comment tree from the response of the
+    /// `comments/{article}` endpoint, which is a pair of
+    /// listings: one with the post and one with its comments.
+    ///
+    /// # Arguments
+    ///
+    /// * `api` - the API client
+    /// * `data` - the response body
+    pub fn from_response<H>(api: H, data: &Value) -> Result<Self, ApiError>
+    where
+        H: ApiHandle<'a, Client = A>,
+        A: ApiClient<'a, Handle = H>,
+    {
+        let post = data[0]["data"]["children"]
+            .get(0)
+            .ok_or_else(|| ApiError::Other(String::from("Post not found in response")))?;
+        Ok(CommentTree {
+            post: Post::from_listing_item(api.clone(), post)?,
+            comments: parse_nodes(api, &data[1])?,
+        })
+    }
+
+    /// Walk the tree's comments depth-first, in the order that
+    /// they would be displayed, skipping placeholders.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let authors: Vec<&str> = tree.iter().map(|c| c.data.author.as_str()).collect();
+    /// ```
+    pub fn iter(&self) -> Comments<'_, 'a, A> {
+        Comments {
+            stack: vec![self.comments.iter()],
+        }
+    }
+}
+
+impl<'a> CommentTree<'a> {
+    /// Replace the tree's "load more comments" placeholders with
+    /// the comments they stand for, via `api/morechildren`.
+    ///
+    /// Placeholders are expanded depth-first, requesting up to
+    /// 100 comments at a time. Expanded comments can contain
+    /// placeholders themselves, which are expanded in turn.
+    ///
+    /// Returns the number of placeholders that were expanded.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - the most placeholders to expand, or `None` for all of them
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let mut tree = api.get_comments("abc123")?;
+    /// tree.expand_more(Some(10))?;
+    /// ```
+    pub fn expand_more(&mut self, limit: Option<usize>) -> Result<usize, ApiError> {
+        let api = self.post.api;
+        let link_id = self.post.data.fullname.clone();
+        let mut fetch = |more: &MoreComments| fetch_more(api, &link_id, more);
+        let mut requested = HashSet::new();
+        let mut expanded = 0;
+        loop {
+            if let Some(limit) = limit {
+                if expanded >= limit {
+                    break;
+                }
+            }
+            if !expand_first(&mut self.comments, &mut fetch, &mut requested)? {
+                break;
+            }
+            expanded += 1;
+        }
+        Ok(expanded)
+    }
+}
+
+/// Depth-first iterator over the comments in a tree.
+///
+/// Created by [`CommentTree::iter`].
+///
+/// [`CommentTree::iter`]: struct.CommentTree.html#method.iter
+pub struct Comments<'t, 'a, A: ApiClient<'a> = Api> {
+    stack: Vec<slice::Iter<'t, CommentNode<'a, A>>>,
+}
+
+impl<'t, 'a, A: ApiClient<'a>> Iterator for Comments<'t, 'a, A> {
+    type Item = &'t Comment<'a, A>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.last_mut()?.next() {
+                Some(CommentNode::Comment(c)) => {
+                    self.stack.push(c.replies.iter());
+                    return Some(c);
+                }
+                Some(CommentNode::More(_)) => continue,
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
 
 #[cfg(test)]
-mod tests {}
+pub(crate) mod tests {
+    use super::{Comment, CommentNode, CommentTree, Distinguish};
+    use crate::errors::ApiError;
+    use crate::models::post::tests::{form_field, sample_item, things_response};
+    use crate::models::VoteDirection;
+    use crate::Api;
+    use lazy_static::lazy_static;
+    use mockito::{mock, Matcher};
+    use serde_json::Value;
+
+    lazy_static! {
+        static ref API: Api = Api::new(std::default::Default::default());
+    }
+
+    pub(crate) fn sample_comment(id: &str, parent_id: &str, depth: u32, replies: &str) -> String {
+        format!(
+            "{{\"kind\":\"t1\",\"data\":{{\"id\":\"{0}\",\"name\":\"t1_{0}\",\"parent_id\":\"{1}\", \
+             \"link_id\":\"t3_abc\",\"author\":\"someone\",\"body\":\"Comment {0}\", \
+             \"body_html\":\"<p>Comment {0}</p>\",\"score\":3,\"depth\":{2}, \
+             \"created_utc\":1560000000.0,\"edited\":false,\"distinguished\":null, \
+             \"stickied\":false,\"replies\":{3}}}}}",
+            id, parent_id, depth, replies
+        )
+    }
+
+    pub(crate) fn listing(children: &[String]) -> String {
+        format!(
+            "{{\"kind\":\"Listing\",\"data\":{{\"after\":null,\"children\":[{}]}}}}",
+            children.join(",")
+        )
+    }
+
+    pub(crate) fn sample_more(id: &str, parent_id: &str, children: &[&str]) -> String {
+        format!(
+            "{{\"kind\":\"more\",\"data\":{{\"id\":\"{0}\",\"name\":\"t1_{0}\", \
+             \"parent_id\":\"{1}\",\"count\":{2},\"depth\":0,\"children\":[{3}]}}}}",
+            id,
+            parent_id,
+            children.len(),
+            children
+                .iter()
+                .map(|c| format!("\"{}\"", c))
+                .collect::<Vec<_>>()
+                .join(",")
+        )
+    }
+
+    pub(crate) fn sample_response() -> String {
+        let reply = sample_comment("ccc", "t1_aaa", 1, "\"\"");
+        format!(
+            "[{},{}]",
+            listing(&[sample_item("abc")]),
+            listing(&[
+                sample_comment("aaa", "t3_abc", 0, &listing(&[reply])),
+                sample_comment("bbb", "t3_abc", 0, "\"\""),
+                sample_more("ddd", "t3_abc", &["ddd", "eee"]),
+            ])
+        )
+    }
+
+    #[test]
+    fn from_response() {
+        let data: Value = serde_json::from_str(&sample_response()).unwrap();
+        let tree = CommentTree::from_response(&*API, &data).unwrap();
+
+        assert_eq!(tree.post.data.id, "abc");
+        assert_eq!(tree.comments.len(), 3);
+        let first = tree.comments[0].as_comment().unwrap();
+        assert_eq!(first.fullname(), "t1_aaa");
+        assert_eq!(first.data.edited, None);
+        assert_eq!(first.replies.len(), 1);
+        assert_eq!(first.replies[0].as_comment().unwrap().data.depth, 1);
+        match &tree.comments[2] {
+            CommentNode::More(more) => assert_eq!(more.children, vec!["ddd", "eee"]),
+            _ => panic!("Expected placeholder"),
+        }
+    }
+
+    #[test]
+    fn edited_timestamp() {
+        let comment = sample_comment("aaa", "t3_abc", 0, "\"\"")
+            .replace("\"edited\":false", "\"edited\":1560000100.0");
+        let item: Value = serde_json::from_str(&comment).unwrap();
+        let comment = super::Comment::from_listing_item(&*API, &item).unwrap();
+
+        assert_eq!(comment.data.edited, Some(1_560_000_100.0));
+    }
+
+    #[test]
+    fn iter() {
+        let data: Value = serde_json::from_str(&sample_response()).unwrap();
+        let tree = CommentTree::from_response(&*API, &data).unwrap();
+        let ids: Vec<&str> = tree.iter().map(|c| c.data.id.as_str()).collect();
+
+        assert_eq!(ids, vec!["aaa", "ccc", "bbb"]);
+    }
+
+    #[test]
+    fn get_comments() {
+        let _m1 = mock("GET", "/comments/abc")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(sample_response())
+            .create();
+        let api = Api::new(std::default::Default::default());
+
+        let tree = api.get_comments("abc").unwrap();
+
+        assert_eq!(tree.iter().count(), 3);
+        _m1.assert();
+    }
+
+    fn more_children_path(children: &str) -> Matcher {
+        Matcher::Regex(format!(
+            r"^/api/morechildren\?.*&children={}&",
+            children.replace(",", "%2C")
+        ))
+    }
+
+    #[test]
+    fn expand_more() {
+        let data: Value = serde_json::from_str(&sample_response()).unwrap();
+        let mut tree = CommentTree::from_response(&*API, &data).unwrap();
+        let body = things_response(&[
+            sample_comment("ddd", "t3_abc", 0, "\"\""),
+            sample_comment("fff", "t1_ddd", 1, "\"\""),
+            sample_comment("eee", "t3_abc", 0, "\"\""),
+        ]);
+        let _m1 = mock("GET", more_children_path("ddd,eee"))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .create();
+
+        assert_eq!(tree.expand_more(None).unwrap(), 1);
+
+        let ids: Vec<&str> = tree.iter().map(|c| c.data.id.as_str()).collect();
+        assert_eq!(ids, vec!["aaa", "ccc", "bbb", "ddd", "fff", "eee"]);
+        assert_eq!(tree.comments.len(), 4);
+        _m1.assert();
+    }
+
+    #[test]
+    fn expand_more_batches() {
+        let ids: Vec<String> = (0..150).map(|i| format!("c{}", i)).collect();
+        let id_refs: Vec<&str> = ids.iter().map(String::as_str).collect();
+        let response = format!(
+            "[{},{}]",
+            listing(&[sample_item("abc")]),
+            listing(&[sample_more("mmm", "t3_abc", &id_refs)])
+        );
+        let data: Value = serde_json::from_str(&response).unwrap();
+        let mut tree = CommentTree::from_response(&*API, &data).unwrap();
+        let _m1 = mock("GET", more_children_path(&id_refs[..100].join(",")))
+            .with_status(200)
+            .with_body(things_response(&[sample_comment(
+                "c0", "t3_abc", 0, "\"\"",
+            )]))
+            .create();
+        let _m2 = mock("GET", more_children_path(&id_refs[100..].join(",")))
+            .with_status(200)
+            .with_body(things_response(&[sample_more("nnn", "t3_abc", &["c149"])]))
+            .create();
+        let _m3 = mock("GET", more_children_path("c149"))
+            .with_status(200)
+            .with_body(things_response(&[]))
+            .expect(0)
+            .create();
+
+        assert_eq!(tree.expand_more(Some(1)).unwrap(), 1);
+
+        assert_eq!(tree.comments.len(), 2);
+        assert_eq!(tree.comments[0].as_comment().unwrap().data.id, "c0");
+        match &tree.comments[1] {
+            CommentNode::More(more) => assert_eq!(more.children, vec!["c149"]),
+            _ => panic!("Expected placeholder"),
+        }
+        _m1.assert();
+        _m2.assert();
+        _m3.assert();
+    }
+
+    #[test]
+    fn expand_more_unavailable() {
+        let response = format!(
+            "[{},{}]",
+            listing(&[sample_item("abc")]),
+            listing(&[sample_more("mmm", "t3_abc", &["gone"])])
+        );
+        let data: Value = serde_json::from_str(&response).unwrap();
+        let mut tree = CommentTree::from_response(&*API, &data).unwrap();
+        // the API can't load the comment, so it returns the same placeholder
+        let _m1 = mock("GET", more_children_path("gone"))
+            .with_status(200)
+            .with_body(things_response(&[sample_more("mmm", "t3_abc", &["gone"])]))
+            .create();
+
+        assert_eq!(tree.expand_more(None).unwrap(), 1);
+
+        assert_eq!(tree.comments.len(), 1);
+        _m1.assert();
+    }
+
+    #[test]
+    fn expand_more_errors() {
+        let data: Value = serde_json::from_str(&sample_response()).unwrap();
+        let mut tree = CommentTree::from_response(&*API, &data).unwrap();
+        let _m1 = mock("GET", more_children_path("ddd,eee"))
+            .with_status(200)
+            .with_body("{\"json\":{\"errors\":[[\"INVALID_ID\",\"bad id\",null]]}}")
+            .create();
+
+        match tree.expand_more(None) {
+            Err(ApiError::Reddit(errors)) => assert_eq!(errors[0].code, "INVALID_ID"),
+            _ => panic!("Expected Reddit error"),
+        }
+    }
+
+    fn sample(api: &Api) -> Comment<'_> {
+        let item: Value =
+            serde_json::from_str(&sample_comment("aaa", "t3_abc", 0, "\"\"")).unwrap();
+        Comment::from_listing_item(api, &item).unwrap()
+    }
+
+    #[test]
+    fn reply() {
+        let _m1 = mock("POST", "/api/comment")
+            .match_body(form_field("thing_id=t1_aaa"))
+            .with_status(200)
+            .with_body(things_response(&[sample_comment(
+                "ccc", "t1_aaa", 1, "\"\"",
+            )]))
+            .create();
+        let api = Api::new(std::default::Default::default());
+
+        let reply = sample(&api).reply("Hi").unwrap();
+
+        assert_eq!(reply.data.parent_id, "t1_aaa");
+        assert_eq!(reply.data.depth, 1);
+        _m1.assert();
+    }
+
+    #[test]
+    fn edit() {
+        let updated = sample_comment("aaa", "t3_abc", 0, "\"\"")
+            .replace("Comment aaa", "Updated")
+            .replace("\"edited\":false", "\"edited\":1560000100.0");
+        let _m1 = mock("POST", "/api/editusertext")
+            .match_body(form_field("text=Updated"))
+            .with_status(200)
+            .with_body(things_response(&[updated]))
+            .create();
+        let api = Api::new(std::default::Default::default());
+
+        let comment = sample(&api).edit("Updated").unwrap();
+
+        assert_eq!(comment.data.body, "Updated");
+        assert!(comment.data.edited.is_some());
+        _m1.assert();
+    }
+
+    #[test]
+    fn distinguish_values() {
+        assert_eq!(Distinguish::Moderator.as_str(), "yes");
+        assert_eq!(Distinguish::Admin.as_str(), "admin");
+        assert_eq!(Distinguish::Special.as_str(), "special");
+        assert_eq!(Distinguish::None.as_str(), "no");
+    }
+
+    #[test]
+    fn distinguish() {
+        let distinguished = sample_comment("aaa", "t3_abc", 0, "\"\"")
+            .replace("\"distinguished\":null", "\"distinguished\":\"moderator\"")
+            .replace("\"stickied\":false", "\"stickied\":true");
+        let _m1 = mock("POST", "/api/distinguish")
+            .match_body(form_field("sticky=true"))
+            .with_status(200)
+            .with_body(things_response(&[distinguished]))
+            .create();
+        let api = Api::new(std::default::Default::default());
+
+        let comment = sample(&api).sticky().unwrap();
+
+        assert_eq!(comment.data.distinguished, Some(String::from("moderator")));
+        assert!(comment.data.stickied);
+        _m1.assert();
+    }
+
+    #[test]
+    fn simple_actions() {
+        let _m1 = mock("POST", "/api/del")
+            .match_body(form_field("id=t1_aaa"))
+            .with_status(200)
+            .with_body("{}")
+            .create();
+        let _m2 = mock("POST", "/api/vote")
+            .match_body(form_field("dir=1"))
+            .with_status(200)
+            .with_body("{}")
+            .create();
+        let _m3 = mock("POST", "/api/save")
+            .match_body(form_field("id=t1_aaa"))
+            .with_status(200)
+            .with_body("{}")
+            .create();
+        let _m4 = mock("POST", "/api/report")
+            .match_body(form_field("thing_id=t1_aaa"))
+            .with_status(200)
+            .with_body("{}")
+            .create();
+        let api = Api::new(std::default::Default::default());
+        let comment = sample(&api);
+
+        comment.delete().unwrap();
+        comment.vote(VoteDirection::Up).unwrap();
+        comment.save(None).unwrap();
+        comment.report("Rude").unwrap();
+
+        _m1.assert();
+        _m2.assert();
+        _m3.assert();
+        _m4.assert();
+    }
+}
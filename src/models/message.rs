@@ -0,0 +1,412 @@
+//! Struct-based access to the inbox and private messages.
+//!
+//! Get the logged-in user's inbox with:
+//!
+//! ```rust,no_run,ignore
+//! let inbox = api.get_inbox();
+//! for message in inbox.get_unread(25)? {
+//!     println!("{}: {}", message.data.subject, message.data.body);
+//!     message.mark_read()?;
+//! }
+//! ```
+
+use super::actions;
+use super::comment;
+use super::stream::{Stream, StreamOptions};
+use super::{from_thing, ApiClient, ApiHandle};
+use crate::query_listing::QueryListingRequest;
+use crate::{Api, ApiError};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// The kind of private message items in listings.
+pub const KIND: &str = "t4";
+
+/// A message's data, as returned by the API for inbox items.
+///
+/// Comment replies and username mentions show up in the inbox as
+/// `t1` items with the same fields, with `was_comment` set.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct MessageData {
+    /// The message's id, i.e. "ghi789"
+    pub id: String,
+    /// The message's fullname, i.e. "t4_ghi789"
+    #[serde(rename = "name")]
+    pub fullname: String,
+    /// The sender's username, if sent by a user
+    #[serde(default)]
+    pub author: Option<String>,
+    /// The recipient's username, or "#subreddit" for modmail
+    #[serde(default)]
+    pub dest: Option<String>,
+    /// The message's subject
+    #[serde(default)]
+    pub subject: String,
+    /// The message's markdown text
+    #[serde(default)]
+    pub body: String,
+    /// The message's text rendered as HTML
+    #[serde(default)]
+    pub body_html: String,
+    /// When the message was sent, in seconds since the epoch
+    pub created_utc: f64,
+    /// Whether the message is unread
+    #[serde(default, rename = "new")]
+    pub unread: bool,
+    /// Whether the item is a comment reply or mention
+    #[serde(default)]
+    pub was_comment: bool,
+    /// The fullname of the message or comment being replied to
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    /// The fullname of the first message in the conversation
+    #[serde(default)]
+    pub first_message_name: Option<String>,
+    /// For comments, the relative URL of the comment in context
+    #[serde(default)]
+    pub context: String,
+    /// For comments and subreddit messages, the subreddit's name
+    #[serde(default)]
+    pub subreddit: Option<String>,
+    /// How the message is distinguished, i.e. "moderator"
+    #[serde(default)]
+    pub distinguished: Option<String>,
+}
+
+/// Maps to a single inbox item: a private message,
+/// comment reply or username mention.
+///
+/// The `A` type parameter is the API client that the struct
+/// was retrieved from, either `Api` or `AsyncApi`.
+pub struct Message<'a, A: ApiClient<'a> = Api> {
+    /// Reference to the source `Api` struct. Used for calling API endpoints.
+    pub api: A::Handle,
+    /// Message's data.
+    pub data: MessageData,
+}
+
+impl<'a, A: ApiClient<'a>> Clone for Message<'a, A> {
+    fn clone(&self) -> Self {
+        Message {
+            api: self.api.clone(),
+            data: self.data.clone(),
+        }
+    }
+}
+
+impl<'a, A: ApiClient<'a>> Message<'a, A> {
+    /// Create a message struct from a `t4` or `t1` item in a listing.
+    ///
+    /// # Arguments
+    ///
+    /// * `api` - the API client
+    /// * `item` - the listing item
+    pub fn from_listing_item<H>(api: H, item: &Value) -> Result<Self, ApiError>
+    where
+        H: ApiHandle<'a, Client = A>,
+        A: ApiClient<'a, Handle = H>,
+    {
+        let kind = if item["kind"] == comment::KIND {
+            comment::KIND
+        } else {
+            KIND
+        };
+        Ok(Message {
+            api,
+            data: from_thing(item, kind)?,
+        })
+    }
+
+    /// Get the message's fullname.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let fullname = message.fullname();
+    /// ```
+    pub fn fullname(&self) -> &str {
+        &self.data.fullname
+    }
+}
+
+impl<'a> Message<'a> {
+    /// Reply to the message.
+    ///
+    /// For comment replies and mentions, this replies to
+    /// the comment, returning the new comment's data.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - the reply's markdown text
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let reply = message.reply("Thanks!")?;
+    /// ```
+    pub fn reply(&self, text: &str) -> Result<Message<'a>, ApiError> {
+        let item = actions::reply(self.api, self.fullname(), text)?;
+        Message::from_listing_item(self.api, &item)
+    }
+
+    /// Mark the message as read.
+    pub fn mark_read(&self) -> Result<(), ApiError> {
+        actions::act(self.api, "api/read_message", self.fullname())
+    }
+
+    /// Mark the message as unread.
+    pub fn mark_unread(&self) -> Result<(), ApiError> {
+        actions::act(self.api, "api/unread_message", self.fullname())
+    }
+
+    /// Delete the message from the inbox.
+    pub fn delete(&self) -> Result<(), ApiError> {
+        actions::act(self.api, "api/del_msg", self.fullname())
+    }
+
+    /// Block the message's author.
+    pub fn block(&self) -> Result<(), ApiError> {
+        actions::act(self.api, "api/block", self.fullname())
+    }
+}
+
+/// Maps to the logged-in user's inbox.
+pub struct Inbox<'a> {
+    /// Reference to the source `Api` struct. Used for calling API endpoints.
+    pub api: &'a Api,
+}
+
+impl<'a> Inbox<'a> {
+    /// Get `count` items from one of the inbox's listings.
+    fn get_listing(&self, path: &str, count: u64) -> Result<Vec<Message<'a>>, ApiError> {
+        let ql = QueryListingRequest::new(path, count.min(100), 0);
+        self.api
+            .listing_iter(ql)
+            .take(count as usize)
+            .map(|item| Message::from_listing_item(self.api, &item?))
+            .collect()
+    }
+
+    /// Get the newest `count` items in the inbox.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of items to retrieve
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let messages = inbox.get_inbox(25)?;
+    /// ```
+    pub fn get_inbox(&self, count: u64) -> Result<Vec<Message<'a>>, ApiError> {
+        self.get_listing("message/inbox", count)
+    }
+
+    /// Get the newest `count` unread items in the inbox.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of items to retrieve
+    pub fn get_unread(&self, count: u64) -> Result<Vec<Message<'a>>, ApiError> {
+        self.get_listing("message/unread", count)
+    }
+
+    /// Get the newest `count` username mentions.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of items to retrieve
+    pub fn get_mentions(&self, count: u64) -> Result<Vec<Message<'a>>, ApiError> {
+        self.get_listing("message/mentions", count)
+    }
+
+    /// Get the newest `count` messages that were sent.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of items to retrieve
+    pub fn get_sent(&self, count: u64) -> Result<Vec<Message<'a>>, ApiError> {
+        self.get_listing("message/sent", count)
+    }
+
+    /// Send a private message.
+    ///
+    /// # Arguments
+    ///
+    /// * `to` - the recipient's username, or "/r/name" for a subreddit's moderators
+    /// * `subject` - the message's subject
+    /// * `text` - the message's markdown text
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// inbox.send("some-username", "Hello", "Hello there!")?;
+    /// ```
+    pub fn send(&self, to: &str, subject: &str, text: &str) -> Result<(), ApiError> {
+        let mut form_data = HashMap::new();
+        form_data.insert("to", to);
+        form_data.insert("subject", subject);
+        form_data.insert("text", text);
+        self.api.post_json("api/compose", form_data)?;
+        Ok(())
+    }
+
+    /// Mark every item in the inbox as read.
+    pub fn mark_all_read(&self) -> Result<(), ApiError> {
+        self.api
+            .post_json("api/read_all_messages", HashMap::new())?;
+        Ok(())
+    }
+
+    /// Stream new unread items as they arrive.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - the stream's options
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// for message in inbox.stream_unread(StreamOptions::new()) {
+    ///     let message = message?;
+    ///     message.reply("Got it!")?;
+    ///     message.mark_read()?;
+    /// }
+    /// ```
+    pub fn stream_unread(&self, options: StreamOptions) -> Stream<'a, Message<'a>> {
+        Stream::new(
+            self.api,
+            "message/unread",
+            options,
+            Message::from_listing_item,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Inbox, Message};
+    use crate::models::comment::tests::{listing, sample_comment};
+    use crate::models::post::tests::{form_field, things_response};
+    use crate::models::stream::StreamOptions;
+    use crate::Api;
+    use mockito::mock;
+    use serde_json::Value;
+    use std::time::Duration;
+
+    fn sample_message(id: &str) -> String {
+        format!(
+            "{{\"kind\":\"t4\",\"data\":{{\"id\":\"{0}\",\"name\":\"t4_{0}\",\"author\":\"someone\", \
+             \"dest\":\"bot\",\"subject\":\"Hi\",\"body\":\"Message {0}\",\"created_utc\":1560000000.0, \
+             \"new\":true,\"was_comment\":false,\"parent_id\":null,\"first_message_name\":null, \
+             \"context\":\"\",\"subreddit\":null,\"distinguished\":null}}}}",
+            id
+        )
+    }
+
+    #[test]
+    fn from_listing_item() {
+        let api = Api::new(std::default::Default::default());
+        let item: Value = serde_json::from_str(&sample_message("ghi")).unwrap();
+        let message = Message::from_listing_item(&api, &item).unwrap();
+
+        assert_eq!(message.fullname(), "t4_ghi");
+        assert_eq!(message.data.author, Some(String::from("someone")));
+        assert!(message.data.unread);
+        assert!(!message.data.was_comment);
+
+        let item: Value = serde_json::from_str(
+            &sample_comment("aaa", "t3_abc", 0, "\"\"")
+                .replace("\"stickied\"", "\"was_comment\":true,\"stickied\""),
+        )
+        .unwrap();
+        let mention = Message::from_listing_item(&api, &item).unwrap();
+
+        assert_eq!(mention.fullname(), "t1_aaa");
+        assert!(mention.data.was_comment);
+    }
+
+    #[test]
+    fn get_unread() {
+        let _m1 = mock("GET", "/message/unread?limit=2&show=all")
+            .with_status(200)
+            .with_body(format!(
+                "{{\"kind\":\"Listing\",\"data\":{{\"after\":null,\"children\":[{},{}]}}}}",
+                sample_message("aaa"),
+                sample_message("bbb")
+            ))
+            .create();
+        let api = Api::new(std::default::Default::default());
+
+        let messages = Inbox { api: &api }.get_unread(2).unwrap();
+
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].data.body, "Message bbb");
+        _m1.assert();
+    }
+
+    #[test]
+    fn send() {
+        let _m1 = mock("POST", "/api/compose")
+            .match_body(form_field("to=someone"))
+            .with_status(200)
+            .with_body("{\"json\":{\"errors\":[]}}")
+            .create();
+        let api = Api::new(std::default::Default::default());
+
+        Inbox { api: &api }.send("someone", "Hi", "Hello").unwrap();
+
+        _m1.assert();
+    }
+
+    #[test]
+    fn actions() {
+        let _m1 = mock("POST", "/api/comment")
+            .match_body(form_field("thing_id=t4_ggg"))
+            .with_status(200)
+            .with_body(things_response(&[sample_message("hhh")]))
+            .create();
+        let _m2 = mock("POST", "/api/read_message")
+            .match_body(form_field("id=t4_ggg"))
+            .with_status(200)
+            .with_body("{}")
+            .create();
+        let _m3 = mock("POST", "/api/block")
+            .match_body(form_field("id=t4_ggg"))
+            .with_status(200)
+            .with_body("{}")
+            .create();
+        let api = Api::new(std::default::Default::default());
+        let item: Value = serde_json::from_str(&sample_message("ggg")).unwrap();
+        let message = Message::from_listing_item(&api, &item).unwrap();
+
+        assert_eq!(message.reply("Thanks").unwrap().fullname(), "t4_hhh");
+        message.mark_read().unwrap();
+        message.block().unwrap();
+
+        _m1.assert();
+        _m2.assert();
+        _m3.assert();
+    }
+
+    #[test]
+    fn stream_unread() {
+        let _m1 = mock("GET", "/message/unread?limit=5&show=all")
+            .with_status(200)
+            .with_body(listing(&[sample_message("bbb"), sample_message("aaa")]))
+            .create();
+        let api = Api::new(std::default::Default::default());
+        let options = StreamOptions::new()
+            .limit(5)
+            .min_delay(Duration::from_millis(0));
+
+        let ids: Vec<String> = Inbox { api: &api }
+            .stream_unread(options)
+            .take(2)
+            .map(|m| m.unwrap().data.id)
+            .collect();
+
+        assert_eq!(ids, vec!["aaa", "bbb"]);
+    }
+}
@@ -7,7 +7,148 @@
 //! in this module, relying on this library to abstract-away those
 //! individual API calls to make interacting with the API simpler.
 
+mod actions;
 pub mod comment;
+pub mod message;
+pub mod moderation;
+pub mod modmail;
 pub mod post;
+pub mod stream;
 pub mod subreddit;
 pub mod user;
+pub mod wiki;
+
+#[cfg(feature = "async")]
+use crate::async_api::AsyncApi;
+use crate::{Api, ApiError};
+use comment::Comment;
+use post::Post;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// An API client that the structs in this module can be retrieved
+/// from, either `Api` or `AsyncApi`.
+///
+/// Structs from an `Api` borrow it. Structs from an `AsyncApi` hold
+/// their own clone of it, which is cheap, so that they can be moved
+/// into futures and onto other threads.
+pub trait ApiClient<'a> {
+    /// How the structs hold on to the client.
+    type Handle: ApiHandle<'a, Client = Self>;
+}
+
+/// How the structs in this module hold on to the API client that
+/// they were retrieved from: `&Api` or `AsyncApi`.
+pub trait ApiHandle<'a>: Clone {
+    /// The client that this is a handle to.
+    type Client: ApiClient<'a, Handle = Self>;
+}
+
+impl<'a> ApiClient<'a> for Api {
+    type Handle = &'a Api;
+}
+
+impl<'a> ApiHandle<'a> for &'a Api {
+    type Client = Api;
+}
+
+#[cfg(feature = "async")]
+impl<'a> ApiClient<'a> for AsyncApi {
+    type Handle = AsyncApi;
+}
+
+#[cfg(feature = "async")]
+impl<'a> ApiHandle<'a> for AsyncApi {
+    type Client = AsyncApi;
+}
+
+/// The direction of a vote on a post or comment.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VoteDirection {
+    /// An upvote.
+    Up,
+    /// A downvote.
+    Down,
+    /// Removes an existing vote.
+    Clear,
+}
+
+impl VoteDirection {
+    /// The value to pass to the vote endpoint.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            VoteDirection::Up => "1",
+            VoteDirection::Down => "-1",
+            VoteDirection::Clear => "0",
+        }
+    }
+}
+
+/// An item in a listing that has both posts and comments.
+pub enum Item<'a, A: ApiClient<'a> = Api> {
+    /// A post
+    Post(Post<'a, A>),
+    /// A comment, without its replies
+    Comment(Comment<'a, A>),
+}
+
+impl<'a, A: ApiClient<'a>> Clone for Item<'a, A> {
+    fn clone(&self) -> Self {
+        match self {
+            Item::Post(p) => Item::Post(p.clone()),
+            Item::Comment(c) => Item::Comment(c.clone()),
+        }
+    }
+}
+
+impl<'a, A: ApiClient<'a>> Item<'a, A> {
+    /// Create an item from a `t3` or `t1` item in a listing.
+    ///
+    /// # Arguments
+    ///
+    /// * `api` - the API client
+    /// * `item` - the listing item
+    pub fn from_listing_item<H>(api: H, item: &Value) -> Result<Self, ApiError>
+    where
+        H: ApiHandle<'a, Client = A>,
+        A: ApiClient<'a, Handle = H>,
+    {
+        if item["kind"] == post::KIND {
+            Ok(Item::Post(Post::from_listing_item(api, item)?))
+        } else {
+            Ok(Item::Comment(Comment::from_listing_item(api, item)?))
+        }
+    }
+
+    /// Get the item's fullname.
+    pub fn fullname(&self) -> &str {
+        match self {
+            Item::Post(p) => p.fullname(),
+            Item::Comment(c) => c.fullname(),
+        }
+    }
+}
+
+/// Deserialize the `data` of a listing item ("thing"),
+/// checking that its `kind` is the expected one.
+pub(crate) fn from_thing<T: DeserializeOwned>(item: &Value, kind: &str) -> Result<T, ApiError> {
+    if item["kind"] != kind {
+        return Err(ApiError::Other(format!(
+            "Expected item of kind '{}', got {}",
+            kind, item["kind"]
+        )));
+    }
+    Ok(serde_json::from_value(item["data"].clone())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VoteDirection;
+
+    #[test]
+    fn vote_direction() {
+        assert_eq!(VoteDirection::Up.as_str(), "1");
+        assert_eq!(VoteDirection::Down.as_str(), "-1");
+        assert_eq!(VoteDirection::Clear.as_str(), "0");
+    }
+}
@@ -0,0 +1,601 @@
+//! Moderation of posts and comments.
+//!
+//! Items from a subreddit's moderation queues are acted on with:
+//!
+//! ```rust,no_run,ignore
+//! for item in api.get_subreddit("rust")?.get_modqueue(25)? {
+//!     item.approve()?;
+//! }
+//! ```
+
+use super::actions;
+use super::{from_thing, Item};
+use crate::{Api, ApiError};
+use serde::{Deserialize, Deserializer};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// A report made by users, as counted by the API.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UserReport {
+    /// The report's reason, if one was given
+    pub reason: Option<String>,
+    /// How many users reported the item for the reason
+    pub count: u64,
+}
+
+/// A report made by a moderator.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModReport {
+    /// The report's reason
+    pub reason: String,
+    /// The reporting moderator's username
+    pub moderator: String,
+}
+
+/// The moderation state of a post or comment.
+///
+/// Only the subreddit's moderators can see these fields, so
+/// they are left empty for everyone else.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct ModerationData {
+    /// The number of reports on the item
+    #[serde(default)]
+    pub num_reports: Option<u64>,
+    /// The reports made by users
+    #[serde(default, deserialize_with = "user_reports")]
+    pub user_reports: Vec<UserReport>,
+    /// The reports made by moderators
+    #[serde(default, deserialize_with = "mod_reports")]
+    pub mod_reports: Vec<ModReport>,
+    /// Whether further reports on the item are ignored
+    #[serde(default)]
+    pub ignore_reports: bool,
+    /// Whether the item was approved
+    #[serde(default)]
+    pub approved: bool,
+    /// The username of the moderator that approved the item
+    #[serde(default)]
+    pub approved_by: Option<String>,
+    /// Whether the item was removed
+    #[serde(default)]
+    pub removed: bool,
+    /// The username of the moderator that removed the item
+    #[serde(default, rename = "banned_by", deserialize_with = "removed_by")]
+    pub removed_by: Option<String>,
+    /// Whether the item was removed as spam
+    #[serde(default)]
+    pub spam: bool,
+}
+
+/// Parse the `[reason, count, ...]` arrays of `user_reports`.
+fn user_reports<'de, D>(deserializer: D) -> Result<Vec<UserReport>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let reports: Option<Vec<Vec<Value>>> = Deserialize::deserialize(deserializer)?;
+    Ok(reports
+        .unwrap_or_default()
+        .iter()
+        .map(|report| UserReport {
+            reason: report.first().and_then(Value::as_str).map(str::to_owned),
+            count: report.get(1).and_then(Value::as_u64).unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// Parse the `[reason, moderator]` arrays of `mod_reports`.
+fn mod_reports<'de, D>(deserializer: D) -> Result<Vec<ModReport>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let reports: Option<Vec<Vec<Value>>> = Deserialize::deserialize(deserializer)?;
+    let field = |report: &[Value], index: usize| {
+        report
+            .get(index)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_owned()
+    };
+    Ok(reports
+        .unwrap_or_default()
+        .iter()
+        .map(|report| ModReport {
+            reason: field(report, 0),
+            moderator: field(report, 1),
+        })
+        .collect())
+}
+
+/// Parse the `banned_by` field, which is `true` instead of
+/// a username for items that the spam filter removed.
+fn removed_by<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: Value = Deserialize::deserialize(deserializer)?;
+    Ok(value.as_str().map(str::to_owned))
+}
+
+/// The kind of moderation log entries in listings.
+pub const MOD_ACTION_KIND: &str = "modaction";
+
+/// An entry in a subreddit's moderation log, from a `modaction` item.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct ModAction {
+    /// The entry's id, i.e. "ModAction_0a1b2c3d-..."
+    pub id: String,
+    /// The type of action taken, i.e. "removelink" or "banuser"
+    pub action: String,
+    /// The username of the moderator that took the action
+    #[serde(rename = "mod")]
+    pub moderator: String,
+    /// The fullname of the item or user acted on, if any
+    #[serde(default)]
+    pub target_fullname: Option<String>,
+    /// The username of the author of the item, or of the user acted on
+    #[serde(default)]
+    pub target_author: Option<String>,
+    /// The permalink of the item acted on
+    #[serde(default)]
+    pub target_permalink: Option<String>,
+    /// Further details on the action, i.e. a ban's duration
+    #[serde(default)]
+    pub details: Option<String>,
+    /// A description of the action, i.e. a ban's reason
+    #[serde(default)]
+    pub description: Option<String>,
+    /// When the action was taken, in seconds since the epoch
+    pub created_utc: f64,
+}
+
+impl ModAction {
+    /// Create a log entry from a `modaction` item in a listing.
+    ///
+    /// # Arguments
+    ///
+    /// * `item` - the listing item
+    pub fn from_listing_item(item: &Value) -> Result<Self, ApiError> {
+        from_thing(item, MOD_ACTION_KIND)
+    }
+}
+
+/// Builder struct for the options used when removing an item.
+#[derive(Clone, Debug)]
+pub struct RemoveOptions<'a> {
+    /// Whether to remove the item as spam, training the spam filter
+    pub spam: bool,
+    /// The id of one of the subreddit's removal reasons
+    pub reason_id: Option<&'a str>,
+    /// A note on the removal for the other moderators
+    pub mod_note: Option<&'a str>,
+}
+
+impl<'a> Default for RemoveOptions<'a> {
+    fn default() -> Self {
+        RemoveOptions::new()
+    }
+}
+
+impl<'a> RemoveOptions<'a> {
+    /// Construct a new builder.
+    pub fn new() -> Self {
+        RemoveOptions {
+            spam: false,
+            reason_id: None,
+            mod_note: None,
+        }
+    }
+
+    /// Override the `spam` field.
+    pub fn spam(mut self, spam: bool) -> Self {
+        self.spam = spam;
+        self
+    }
+
+    /// Override the `reason_id` field.
+    pub fn reason_id(mut self, reason_id: Option<&'a str>) -> Self {
+        self.reason_id = reason_id;
+        self
+    }
+
+    /// Override the `mod_note` field.
+    pub fn mod_note(mut self, mod_note: Option<&'a str>) -> Self {
+        self.mod_note = mod_note;
+        self
+    }
+}
+
+/// A user's relationship with a subreddit, from the `about/muted`
+/// and `about/contributors` listings.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Relationship {
+    /// The user's name
+    pub name: String,
+    /// The user's fullname, i.e. "t2_1w72"
+    #[serde(rename = "id")]
+    pub fullname: String,
+    /// When the relationship started, in seconds since the epoch
+    pub date: f64,
+}
+
+/// A user banned from a subreddit, from the `about/banned` listing.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct BannedUser {
+    /// The user's name
+    pub name: String,
+    /// The user's fullname, i.e. "t2_1w72"
+    #[serde(rename = "id")]
+    pub fullname: String,
+    /// When the user was banned, in seconds since the epoch
+    pub date: f64,
+    /// The moderators' note on the ban
+    #[serde(default)]
+    pub note: Option<String>,
+    /// How many days are left on a temporary ban, or `None` if it's permanent
+    #[serde(default)]
+    pub days_left: Option<u64>,
+}
+
+/// A subreddit's moderator, from the `about/moderators` listing.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Moderator {
+    /// The moderator's username
+    pub name: String,
+    /// The moderator's fullname, i.e. "t2_1w72"
+    #[serde(rename = "id")]
+    pub fullname: String,
+    /// When the user became a moderator, in seconds since the epoch
+    pub date: f64,
+    /// The moderator's permissions, i.e. "all" or "posts"
+    #[serde(default, rename = "mod_permissions")]
+    pub permissions: Vec<String>,
+}
+
+/// Builder struct for the options used when banning a user.
+#[derive(Clone, Debug)]
+pub struct BanOptions<'a> {
+    /// The length of the ban in days, or `None` for a permanent ban
+    pub duration: Option<u32>,
+    /// The ban's reason, from the subreddit's rules
+    pub reason: Option<&'a str>,
+    /// A note on the ban for the other moderators
+    pub note: Option<&'a str>,
+    /// A message to the banned user
+    pub message: Option<&'a str>,
+}
+
+impl<'a> Default for BanOptions<'a> {
+    fn default() -> Self {
+        BanOptions::new()
+    }
+}
+
+impl<'a> BanOptions<'a> {
+    /// Construct a new builder.
+    pub fn new() -> Self {
+        BanOptions {
+            duration: None,
+            reason: None,
+            note: None,
+            message: None,
+        }
+    }
+
+    /// Override the `duration` field.
+    pub fn duration(mut self, duration: Option<u32>) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Override the `reason` field.
+    pub fn reason(mut self, reason: Option<&'a str>) -> Self {
+        self.reason = reason;
+        self
+    }
+
+    /// Override the `note` field.
+    pub fn note(mut self, note: Option<&'a str>) -> Self {
+        self.note = note;
+        self
+    }
+
+    /// Override the `message` field.
+    pub fn message(mut self, message: Option<&'a str>) -> Self {
+        self.message = message;
+        self
+    }
+}
+
+impl<'a> Item<'a> {
+    fn api(&self) -> &'a Api {
+        match self {
+            Item::Post(p) => p.api,
+            Item::Comment(c) => c.api,
+        }
+    }
+
+    /// Approve the item, taking it out of the moderation queues.
+    pub fn approve(&self) -> Result<(), ApiError> {
+        actions::act(self.api(), "api/approve", self.fullname())
+    }
+
+    /// Remove the item.
+    ///
+    /// The removal reason, if any, is added with a separate request
+    /// after the item is removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - the removal's options
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// item.remove(RemoveOptions::new().spam(true))?;
+    /// ```
+    pub fn remove(&self, options: RemoveOptions) -> Result<(), ApiError> {
+        let mut form_data = HashMap::new();
+        form_data.insert("id", self.fullname());
+        form_data.insert("spam", if options.spam { "true" } else { "false" });
+        self.api().post_json("api/remove", form_data)?;
+        if options.reason_id.is_none() && options.mod_note.is_none() {
+            return Ok(());
+        }
+        let mut reason = json!({ "item_ids": [self.fullname()] });
+        if let Some(reason_id) = options.reason_id {
+            reason["reason_id"] = json!(reason_id);
+        }
+        if let Some(mod_note) = options.mod_note {
+            reason["mod_note"] = json!(mod_note);
+        }
+        let reason = reason.to_string();
+        let mut form_data = HashMap::new();
+        form_data.insert("json", reason.as_str());
+        self.api().query(
+            "POST",
+            "api/v1/modactions/removal_reasons",
+            None,
+            Some(form_data),
+        )?;
+        Ok(())
+    }
+
+    /// Ignore any further reports on the item.
+    pub fn ignore_reports(&self) -> Result<(), ApiError> {
+        actions::act(self.api(), "api/ignore_reports", self.fullname())
+    }
+
+    /// Stop ignoring reports on the item.
+    pub fn unignore_reports(&self) -> Result<(), ApiError> {
+        actions::act(self.api(), "api/unignore_reports", self.fullname())
+    }
+
+    /// Lock the item, preventing new replies to it.
+    pub fn lock(&self) -> Result<(), ApiError> {
+        actions::act(self.api(), "api/lock", self.fullname())
+    }
+
+    /// Unlock the item.
+    pub fn unlock(&self) -> Result<(), ApiError> {
+        actions::act(self.api(), "api/unlock", self.fullname())
+    }
+
+    /// Mark the item as NSFW. Only posts can be marked.
+    pub fn mark_nsfw(&self) -> Result<(), ApiError> {
+        match self {
+            Item::Post(p) => p.mark_nsfw(),
+            Item::Comment(_) => Err(ApiError::Other(String::from(
+                "Comments can't be marked as NSFW",
+            ))),
+        }
+    }
+
+    /// Remove the item's NSFW mark. Only posts can be marked.
+    pub fn unmark_nsfw(&self) -> Result<(), ApiError> {
+        match self {
+            Item::Post(p) => p.unmark_nsfw(),
+            Item::Comment(_) => Err(ApiError::Other(String::from(
+                "Comments can't be marked as NSFW",
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ModReport, ModerationData, RemoveOptions, UserReport};
+    use crate::errors::ApiError;
+    use crate::models::comment::tests::sample_comment;
+    use crate::models::post::tests::{form_field, sample_item};
+    use crate::models::Item;
+    use crate::Api;
+    use mockito::mock;
+    use serde_json::Value;
+
+    fn item<'a>(api: &'a Api, listing_item: &str) -> Item<'a> {
+        let value: Value = serde_json::from_str(listing_item).unwrap();
+        Item::from_listing_item(api, &value).unwrap()
+    }
+
+    #[test]
+    fn moderation_data() {
+        let data: ModerationData = serde_json::from_str(
+            "{\"num_reports\":3,\"user_reports\":[[\"Spam\",2,false,false],[null,1,false,false]], \
+             \"mod_reports\":[[\"Rule 1\",\"some-mod\"]],\"ignore_reports\":false, \
+             \"approved\":false,\"approved_by\":null,\"removed\":true,\"banned_by\":true, \
+             \"spam\":true}",
+        )
+        .unwrap();
+
+        assert_eq!(data.num_reports, Some(3));
+        assert_eq!(
+            data.user_reports,
+            vec![
+                UserReport {
+                    reason: Some(String::from("Spam")),
+                    count: 2,
+                },
+                UserReport {
+                    reason: None,
+                    count: 1,
+                },
+            ]
+        );
+        assert_eq!(
+            data.mod_reports,
+            vec![ModReport {
+                reason: String::from("Rule 1"),
+                moderator: String::from("some-mod"),
+            }]
+        );
+        assert!(data.removed);
+        // removed by the spam filter rather than a moderator
+        assert_eq!(data.removed_by, None);
+    }
+
+    #[test]
+    fn moderation_data_removed_by_moderator() {
+        let data: ModerationData =
+            serde_json::from_str("{\"removed\":true,\"banned_by\":\"some-mod\"}").unwrap();
+
+        assert!(data.removed);
+        assert_eq!(data.removed_by, Some(String::from("some-mod")));
+    }
+
+    #[test]
+    fn moderation_data_hidden() {
+        let data: ModerationData = serde_json::from_str("{}").unwrap();
+
+        assert_eq!(data, ModerationData::default());
+    }
+
+    #[test]
+    fn approve() {
+        let _m1 = mock("POST", "/api/approve")
+            .match_body(form_field("id=t1_ccc"))
+            .with_status(200)
+            .with_body("{}")
+            .create();
+        let api = Api::new(std::default::Default::default());
+
+        item(&api, &sample_comment("ccc", "t3_abc", 0, "\"\""))
+            .approve()
+            .unwrap();
+
+        _m1.assert();
+    }
+
+    #[test]
+    fn remove_as_spam() {
+        let _m1 = mock("POST", "/api/remove")
+            .match_body(form_field("spam=true"))
+            .with_status(200)
+            .with_body("{}")
+            .create();
+        let _m2 = mock("POST", "/api/v1/modactions/removal_reasons")
+            .with_status(200)
+            .with_body("{}")
+            .expect(0)
+            .create();
+        let api = Api::new(std::default::Default::default());
+
+        item(&api, &sample_item("rsp"))
+            .remove(RemoveOptions::new().spam(true))
+            .unwrap();
+
+        _m1.assert();
+        _m2.assert();
+    }
+
+    #[test]
+    fn remove_with_reason() {
+        let _m1 = mock("POST", "/api/remove")
+            .match_body(form_field("id=t3_rrs"))
+            .with_status(200)
+            .with_body("{}")
+            .create();
+        let _m2 = mock("POST", "/api/v1/modactions/removal_reasons")
+            .match_body(form_field(
+                "json=%7B%22item_ids%22%3A%5B%22t3_rrs%22%5D%2C%22mod_note%22%3A%22Off\\+topic%22%2C\
+                 %22reason_id%22%3A%22r1%22%7D",
+            ))
+            .with_status(200)
+            .with_body("{}")
+            .create();
+        let api = Api::new(std::default::Default::default());
+
+        item(&api, &sample_item("rrs"))
+            .remove(
+                RemoveOptions::new()
+                    .reason_id(Some("r1"))
+                    .mod_note(Some("Off topic")),
+            )
+            .unwrap();
+
+        _m1.assert();
+        _m2.assert();
+    }
+
+    #[test]
+    fn remove_with_reason_id_only() {
+        let _m1 = mock("POST", "/api/remove")
+            .match_body(form_field("id=t3_rro"))
+            .with_status(200)
+            .with_body("{}")
+            .create();
+        let _m2 = mock("POST", "/api/v1/modactions/removal_reasons")
+            .match_body(form_field(
+                "json=%7B%22item_ids%22%3A%5B%22t3_rro%22%5D%2C%22reason_id%22%3A%22r1%22%7D",
+            ))
+            .with_status(200)
+            .with_body("{}")
+            .create();
+        let api = Api::new(std::default::Default::default());
+
+        item(&api, &sample_item("rro"))
+            .remove(RemoveOptions::new().reason_id(Some("r1")))
+            .unwrap();
+
+        _m1.assert();
+        _m2.assert();
+    }
+
+    #[test]
+    fn lock() {
+        let _m1 = mock("POST", "/api/lock")
+            .match_body(form_field("id=t3_lck"))
+            .with_status(200)
+            .with_body("{}")
+            .create();
+        let api = Api::new(std::default::Default::default());
+
+        item(&api, &sample_item("lck")).lock().unwrap();
+
+        _m1.assert();
+    }
+
+    #[test]
+    fn ignore_reports() {
+        let _m1 = mock("POST", "/api/ignore_reports")
+            .match_body(form_field("id=t3_ign"))
+            .with_status(200)
+            .with_body("{}")
+            .create();
+        let api = Api::new(std::default::Default::default());
+
+        item(&api, &sample_item("ign")).ignore_reports().unwrap();
+
+        _m1.assert();
+    }
+
+    #[test]
+    fn mark_nsfw_comment() {
+        let api = Api::new(std::default::Default::default());
+
+        match item(&api, &sample_comment("ccc", "t3_abc", 0, "\"\"")).mark_nsfw() {
+            Err(ApiError::Other(_)) => (),
+            _ => panic!("Expected error"),
+        }
+    }
+}
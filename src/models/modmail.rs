@@ -0,0 +1,601 @@
+//! Struct-based access to the new modmail.
+//!
+//! Get the modmail of the subreddits that the logged-in user moderates with:
+//!
+//! ```rust,no_run,ignore
+//! let modmail = api.get_modmail();
+//! for conversation in modmail.get_conversations(ConversationState::New, 25)? {
+//!     println!("{}", conversation.data.subject);
+//!     conversation.mark_read()?;
+//! }
+//! ```
+
+use super::ApiClient;
+use crate::{parse_json, Api, ApiError};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// The most conversations that `api/mod/conversations` returns per request.
+const CONVERSATIONS_LIMIT: u64 = 100;
+
+/// The states that conversations can be listed by.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConversationState {
+    /// Conversations that no moderator has replied to yet.
+    New,
+    /// Conversations that moderators have replied to.
+    InProgress,
+    /// Archived conversations.
+    Archived,
+    /// Conversations started by moderators with each other.
+    Mod,
+    /// Automated notifications, like ban appeals.
+    Notifications,
+    /// Every conversation.
+    All,
+}
+
+impl ConversationState {
+    /// The value to pass as the `state` parameter.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ConversationState::New => "new",
+            ConversationState::InProgress => "inprogress",
+            ConversationState::Archived => "archived",
+            ConversationState::Mod => "mod",
+            ConversationState::Notifications => "notifications",
+            ConversationState::All => "all",
+        }
+    }
+}
+
+/// How long to mute a user from a subreddit's modmail.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MuteDuration {
+    /// 3 days.
+    ThreeDays,
+    /// 7 days.
+    SevenDays,
+    /// 28 days.
+    TwentyEightDays,
+}
+
+impl MuteDuration {
+    /// The value to pass as the `num_hours` parameter.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MuteDuration::ThreeDays => "72",
+            MuteDuration::SevenDays => "168",
+            MuteDuration::TwentyEightDays => "672",
+        }
+    }
+}
+
+/// A participant in a conversation.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModmailAuthor {
+    /// The user's name
+    pub name: String,
+    /// Whether the user moderates the subreddit
+    #[serde(default)]
+    pub is_mod: bool,
+    /// Whether the user is a Reddit admin
+    #[serde(default)]
+    pub is_admin: bool,
+    /// Whether the user started the conversation
+    #[serde(default)]
+    pub is_op: bool,
+    /// Whether the user is shown as the subreddit rather than by name
+    #[serde(default)]
+    pub is_hidden: bool,
+    /// Whether the user's account was deleted
+    #[serde(default)]
+    pub is_deleted: bool,
+}
+
+/// The subreddit that a conversation belongs to.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModmailOwner {
+    /// The subreddit's name
+    pub display_name: String,
+    /// The subreddit's fullname, i.e. "t5_2qh1i"
+    #[serde(rename = "id")]
+    pub fullname: String,
+}
+
+/// A single message in a conversation.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModmailMessage {
+    /// The message's id
+    pub id: String,
+    /// The message's author
+    pub author: ModmailAuthor,
+    /// The message's markdown text
+    pub body_markdown: String,
+    /// The message's text rendered as HTML
+    #[serde(default, rename = "body")]
+    pub body_html: String,
+    /// When the message was sent, as an ISO 8601 timestamp
+    pub date: String,
+    /// Whether the message is only visible to moderators
+    #[serde(default)]
+    pub is_internal: bool,
+}
+
+/// A conversation's data, as returned by the API.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationData {
+    /// The conversation's id, i.e. "1a2b3"
+    pub id: String,
+    /// The conversation's subject
+    pub subject: String,
+    /// The subreddit that the conversation belongs to
+    pub owner: ModmailOwner,
+    /// The non-moderator in the conversation, if any
+    #[serde(default)]
+    pub participant: Option<ModmailAuthor>,
+    /// Everyone that has sent a message in the conversation
+    #[serde(default)]
+    pub authors: Vec<ModmailAuthor>,
+    /// The conversation's state, 0 for new, 1 for in progress and 2 for archived
+    #[serde(default)]
+    pub state: u32,
+    /// Whether the conversation is only between moderators
+    #[serde(default)]
+    pub is_internal: bool,
+    /// Whether the conversation is highlighted
+    #[serde(default)]
+    pub is_highlighted: bool,
+    /// Whether the conversation is an automated notification
+    #[serde(default)]
+    pub is_auto: bool,
+    /// The number of messages in the conversation
+    #[serde(default)]
+    pub num_messages: u64,
+    /// When the conversation was last updated, as an ISO 8601 timestamp
+    #[serde(default)]
+    pub last_updated: Option<String>,
+    /// When the logged-in user last read the conversation, as an ISO 8601 timestamp
+    #[serde(default)]
+    pub last_unread: Option<String>,
+    /// The ids of the conversation's messages and mod actions, oldest first
+    #[serde(default)]
+    obj_ids: Vec<ModmailObject>,
+}
+
+/// A reference to one of a conversation's messages or mod actions.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+struct ModmailObject {
+    id: String,
+    key: String,
+}
+
+/// Maps to a single modmail conversation.
+///
+/// The `A` type parameter is the API client that the struct
+/// was retrieved from, either `Api` or `AsyncApi`.
+pub struct Conversation<'a, A: ApiClient<'a> = Api> {
+    /// Reference to the source `Api` struct. Used for calling API endpoints.
+    pub api: A::Handle,
+    /// Conversation's data.
+    pub data: ConversationData,
+    /// The conversation's messages included in the response, oldest first.
+    ///
+    /// Conversations from listings only include their latest message.
+    pub messages: Vec<ModmailMessage>,
+}
+
+impl<'a, A: ApiClient<'a>> Clone for Conversation<'a, A> {
+    fn clone(&self) -> Self {
+        Conversation {
+            api: self.api.clone(),
+            data: self.data.clone(),
+            messages: self.messages.clone(),
+        }
+    }
+}
+
+impl<'a, A: ApiClient<'a>> Conversation<'a, A> {
+    /// Create a conversation from its data, picking its
+    /// messages from the response's `messages` object.
+    fn from_data(api: A::Handle, conversation: &Value, messages: &Value) -> Result<Self, ApiError> {
+        let data: ConversationData = serde_json::from_value(conversation.clone())?;
+        let messages = data
+            .obj_ids
+            .iter()
+            .filter(|obj| obj.key == "messages")
+            .filter_map(|obj| messages.get(&obj.id))
+            .map(|message| serde_json::from_value(message.clone()))
+            .collect::<Result<_, _>>()?;
+        Ok(Conversation {
+            api,
+            data,
+            messages,
+        })
+    }
+
+    /// Create a conversation from the response of the
+    /// endpoints that get or update a single conversation.
+    fn from_response(api: A::Handle, data: &Value) -> Result<Self, ApiError> {
+        Conversation::from_data(api, &data["conversation"], &data["messages"])
+    }
+}
+
+impl<'a> Conversation<'a> {
+    /// The conversation's endpoint, with an optional action appended.
+    fn path(&self, action: &str) -> String {
+        if action.is_empty() {
+            format!("api/mod/conversations/{}", self.data.id)
+        } else {
+            format!("api/mod/conversations/{}/{}", self.data.id, action)
+        }
+    }
+
+    /// Send a request to one of the conversation's endpoints,
+    /// returning the updated conversation.
+    fn update(
+        &self,
+        method: &str,
+        action: &str,
+        form_data: Option<HashMap<&str, &str>>,
+    ) -> Result<Conversation<'a>, ApiError> {
+        let mut resp = self
+            .api
+            .query(method, &self.path(action), None, form_data)?;
+        let data: Value = parse_json(&mut resp)?;
+        Conversation::from_response(self.api, &data)
+    }
+
+    /// Get the conversation again, with all of its messages.
+    ///
+    /// # Arguments
+    ///
+    /// * `mark_read` - whether to also mark the conversation as read
+    pub fn refresh(&self, mark_read: bool) -> Result<Conversation<'a>, ApiError> {
+        Modmail { api: self.api }.get_conversation(&self.data.id, mark_read)
+    }
+
+    /// Reply to the conversation, returning it with the new message.
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - markdown text of the reply
+    /// * `internal` - whether to make it a private moderator note
+    /// * `hide_author` - whether to reply as the subreddit rather than by name
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let conversation = conversation.reply("Looking into it.", false, true)?;
+    /// ```
+    pub fn reply(
+        &self,
+        body: &str,
+        internal: bool,
+        hide_author: bool,
+    ) -> Result<Conversation<'a>, ApiError> {
+        let flag = |value: bool| if value { "true" } else { "false" };
+        let mut form_data = HashMap::new();
+        form_data.insert("body", body);
+        form_data.insert("isInternal", flag(internal));
+        form_data.insert("isAuthorHidden", flag(hide_author));
+        self.update("POST", "", Some(form_data))
+    }
+
+    /// Archive the conversation.
+    pub fn archive(&self) -> Result<Conversation<'a>, ApiError> {
+        self.update("POST", "archive", None)
+    }
+
+    /// Move the conversation out of the archive.
+    pub fn unarchive(&self) -> Result<Conversation<'a>, ApiError> {
+        self.update("POST", "unarchive", None)
+    }
+
+    /// Highlight the conversation.
+    pub fn highlight(&self) -> Result<Conversation<'a>, ApiError> {
+        self.update("POST", "highlight", None)
+    }
+
+    /// Remove the conversation's highlight.
+    pub fn unhighlight(&self) -> Result<Conversation<'a>, ApiError> {
+        self.update("DELETE", "highlight", None)
+    }
+
+    /// Mute the conversation's participant, preventing them from
+    /// messaging the subreddit's moderators.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration` - how long to mute the user
+    pub fn mute_user(&self, duration: MuteDuration) -> Result<Conversation<'a>, ApiError> {
+        let mut form_data = HashMap::new();
+        form_data.insert("num_hours", duration.as_str());
+        self.update("POST", "mute", Some(form_data))
+    }
+
+    /// Unmute the conversation's participant.
+    pub fn unmute_user(&self) -> Result<Conversation<'a>, ApiError> {
+        self.update("POST", "unmute", None)
+    }
+
+    /// Mark the conversation as read.
+    pub fn mark_read(&self) -> Result<(), ApiError> {
+        Modmail { api: self.api }.mark_read(&[&self.data.id])
+    }
+
+    /// Mark the conversation as unread.
+    pub fn mark_unread(&self) -> Result<(), ApiError> {
+        Modmail { api: self.api }.mark_unread(&[&self.data.id])
+    }
+}
+
+/// Maps to the modmail of the subreddits that the logged-in user moderates.
+///
+/// The `A` type parameter is the API client that the struct
+/// was retrieved from, either `Api` or `AsyncApi`.
+pub struct Modmail<'a, A: ApiClient<'a> = Api> {
+    /// Reference to the source `Api` struct. Used for calling API endpoints.
+    pub api: A::Handle,
+}
+
+impl<'a> Modmail<'a> {
+    /// Get the `count` most recently updated conversations in the state.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - the state of the conversations to get
+    /// * `count` - number of conversations to retrieve
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let conversations = modmail.get_conversations(ConversationState::InProgress, 50)?;
+    /// ```
+    pub fn get_conversations(
+        &self,
+        state: ConversationState,
+        count: u64,
+    ) -> Result<Vec<Conversation<'a>>, ApiError> {
+        let mut conversations = Vec::new();
+        let mut after: Option<String> = None;
+        while (conversations.len() as u64) < count {
+            let limit = (count - conversations.len() as u64).min(CONVERSATIONS_LIMIT);
+            let limit_param = limit.to_string();
+            let mut query = vec![("state", state.as_str()), ("limit", limit_param.as_str())];
+            if let Some(after) = &after {
+                query.push(("after", after.as_str()));
+            }
+            let mut resp = self
+                .api
+                .query("GET", "api/mod/conversations", Some(query), None)?;
+            let data: Value = parse_json(&mut resp)?;
+            let ids: Vec<String> = serde_json::from_value(data["conversationIds"].clone())?;
+            for id in &ids {
+                conversations.push(Conversation::from_data(
+                    self.api,
+                    &data["conversations"][id],
+                    &data["messages"],
+                )?);
+            }
+            // a short page means there are no more conversations
+            if (ids.len() as u64) < limit {
+                break;
+            }
+            after = ids.last().cloned();
+        }
+        Ok(conversations)
+    }
+
+    /// Get a conversation with all of its messages.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - the conversation's id
+    /// * `mark_read` - whether to also mark the conversation as read
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let conversation = modmail.get_conversation("1a2b3", true)?;
+    /// for message in &conversation.messages {
+    ///     println!("{}: {}", message.author.name, message.body_markdown);
+    /// }
+    /// ```
+    pub fn get_conversation(
+        &self,
+        id: &str,
+        mark_read: bool,
+    ) -> Result<Conversation<'a>, ApiError> {
+        let query = vec![("markRead", if mark_read { "true" } else { "false" })];
+        let path = format!("api/mod/conversations/{}", id);
+        let mut resp = self.api.query("GET", &path, Some(query), None)?;
+        let data: Value = parse_json(&mut resp)?;
+        Conversation::from_response(self.api, &data)
+    }
+
+    /// Mark conversations as read or unread.
+    fn mark(&self, path: &str, ids: &[&str]) -> Result<(), ApiError> {
+        let ids = ids.join(",");
+        let mut form_data = HashMap::new();
+        form_data.insert("conversationIds", ids.as_str());
+        self.api.query("POST", path, None, Some(form_data))?;
+        Ok(())
+    }
+
+    /// Mark conversations as read.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - the conversations' ids
+    pub fn mark_read(&self, ids: &[&str]) -> Result<(), ApiError> {
+        self.mark("api/mod/conversations/read", ids)
+    }
+
+    /// Mark conversations as unread.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - the conversations' ids
+    pub fn mark_unread(&self, ids: &[&str]) -> Result<(), ApiError> {
+        self.mark("api/mod/conversations/unread", ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConversationState, Modmail, MuteDuration};
+    use crate::models::post::tests::form_field;
+    use crate::Api;
+    use mockito::mock;
+
+    fn conversation(id: &str, message_ids: &[&str]) -> String {
+        let obj_ids: Vec<String> = message_ids
+            .iter()
+            .map(|m| format!("{{\"id\":\"{}\",\"key\":\"messages\"}}", m))
+            .collect();
+        format!(
+            "{{\"id\":\"{}\",\"subject\":\"Help\",\"owner\":{{\"displayName\":\"rust\", \
+             \"id\":\"t5_2s7lj\",\"type\":\"subreddit\"}},\"participant\":{{\"name\":\"someone\", \
+             \"isMod\":false,\"isOp\":true}},\"authors\":[],\"state\":0,\"isInternal\":false, \
+             \"isHighlighted\":false,\"isAuto\":false,\"numMessages\":{},\"objIds\":[{}]}}",
+            id,
+            message_ids.len(),
+            obj_ids.join(",")
+        )
+    }
+
+    fn message(id: &str, body: &str) -> String {
+        format!(
+            "{{\"id\":\"{}\",\"author\":{{\"name\":\"someone\",\"isMod\":false}}, \
+             \"bodyMarkdown\":\"{}\",\"body\":\"<p>{}</p>\",\"date\":\"2019-06-08T12:00:00+00:00\", \
+             \"isInternal\":false}}",
+            id, body, body
+        )
+    }
+
+    fn conversation_response(id: &str) -> String {
+        format!(
+            "{{\"conversation\":{},\"messages\":{{\"m1\":{},\"m2\":{}}}}}",
+            conversation(id, &["m1", "m2"]),
+            message("m1", "Hello"),
+            message("m2", "Hi there")
+        )
+    }
+
+    #[test]
+    fn get_conversations() {
+        let _m1 = mock("GET", "/api/mod/conversations?state=new&limit=2")
+            .with_status(200)
+            .with_body(format!(
+                "{{\"conversationIds\":[\"c1\",\"c2\"],\"conversations\":{{\"c1\":{},\"c2\":{}}}, \
+                 \"messages\":{{\"m1\":{},\"m3\":{}}}}}",
+                conversation("c1", &["m1"]),
+                conversation("c2", &["m3"]),
+                message("m1", "Hello"),
+                message("m3", "Help!")
+            ))
+            .create();
+        let api = Api::new(std::default::Default::default());
+
+        let conversations = Modmail { api: &api }
+            .get_conversations(ConversationState::New, 2)
+            .unwrap();
+
+        assert_eq!(conversations.len(), 2);
+        assert_eq!(conversations[0].data.id, "c1");
+        assert_eq!(conversations[0].data.owner.display_name, "rust");
+        assert_eq!(conversations[1].messages[0].body_markdown, "Help!");
+        _m1.assert();
+    }
+
+    #[test]
+    fn get_conversation() {
+        let _m1 = mock("GET", "/api/mod/conversations/c5?markRead=true")
+            .with_status(200)
+            .with_body(conversation_response("c5"))
+            .create();
+        let api = Api::new(std::default::Default::default());
+
+        let conversation = Modmail { api: &api }.get_conversation("c5", true).unwrap();
+
+        assert_eq!(conversation.data.subject, "Help");
+        assert!(conversation.data.participant.unwrap().is_op);
+        assert_eq!(conversation.messages.len(), 2);
+        assert_eq!(conversation.messages[1].body_markdown, "Hi there");
+        assert_eq!(conversation.messages[1].body_html, "<p>Hi there</p>");
+        _m1.assert();
+    }
+
+    #[test]
+    fn reply() {
+        let _m1 = mock("GET", "/api/mod/conversations/c6?markRead=false")
+            .with_status(200)
+            .with_body(conversation_response("c6"))
+            .create();
+        let _m2 = mock("POST", "/api/mod/conversations/c6")
+            .match_body(form_field("isInternal=true"))
+            .with_status(200)
+            .with_body(conversation_response("c6"))
+            .create();
+        let api = Api::new(std::default::Default::default());
+        let conversation = Modmail { api: &api }.get_conversation("c6", false).unwrap();
+
+        let conversation = conversation.reply("Noted", true, false).unwrap();
+
+        assert_eq!(conversation.data.id, "c6");
+        _m1.assert();
+        _m2.assert();
+    }
+
+    #[test]
+    fn actions() {
+        let _m1 = mock("GET", "/api/mod/conversations/c7?markRead=false")
+            .with_status(200)
+            .with_body(conversation_response("c7"))
+            .create();
+        let api = Api::new(std::default::Default::default());
+        let conversation = Modmail { api: &api }.get_conversation("c7", false).unwrap();
+        let mocks: Vec<_> = [
+            ("POST", "/api/mod/conversations/c7/archive"),
+            ("POST", "/api/mod/conversations/c7/highlight"),
+            ("DELETE", "/api/mod/conversations/c7/highlight"),
+        ]
+        .iter()
+        .map(|(method, path)| {
+            mock(method, *path)
+                .with_status(200)
+                .with_body(conversation_response("c7"))
+                .create()
+        })
+        .collect();
+        let _m2 = mock("POST", "/api/mod/conversations/c7/mute")
+            .match_body("num_hours=168")
+            .with_status(200)
+            .with_body(conversation_response("c7"))
+            .create();
+        let _m3 = mock("POST", "/api/mod/conversations/read")
+            .match_body("conversationIds=c7")
+            .with_status(200)
+            .with_body("{}")
+            .create();
+
+        conversation.archive().unwrap();
+        conversation.highlight().unwrap();
+        conversation.unhighlight().unwrap();
+        conversation.mute_user(MuteDuration::SevenDays).unwrap();
+        conversation.mark_read().unwrap();
+
+        for m in mocks {
+            m.assert();
+        }
+        _m1.assert();
+        _m2.assert();
+        _m3.assert();
+    }
+}
@@ -1,29 +1,512 @@
 //! Struct-based access to various post APIs.
 //!
-//! Get a post struct with:
+//! Get post structs with:
 //!
-//! TODO
+//! ```rust,no_run,ignore
+//! let posts = api.get_subreddit("rust")?.get_hot(25)?;
+//! ```
 
-use super::user::User;
-use crate::Api;
+use super::actions;
+use super::comment::{Comment, CommentTree};
+use super::moderation::ModerationData;
+use super::{from_thing, ApiClient, ApiHandle, VoteDirection};
+use crate::{Api, ApiError};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// The kind of post items in listings.
+pub const KIND: &str = "t3";
+
+/// A post's data, as returned by the API for `t3` items.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct PostData {
+    /// The post's id, i.e. "abc123"
+    pub id: String,
+    /// The post's fullname, i.e. "t3_abc123"
+    #[serde(rename = "name")]
+    pub fullname: String,
+    /// The post author's username
+    pub author: String,
+    /// The name of the subreddit the post was made in
+    pub subreddit: String,
+    /// The post's title
+    pub title: String,
+    /// The post's score
+    #[serde(default)]
+    pub score: i64,
+    /// The ratio of upvotes to total votes
+    #[serde(default)]
+    pub upvote_ratio: f64,
+    /// When the post was made, in seconds since the epoch
+    pub created_utc: f64,
+    /// Whether the post is a text post
+    #[serde(default)]
+    pub is_self: bool,
+    /// The post's markdown text, empty for link posts
+    #[serde(default)]
+    pub selftext: String,
+    /// The post's link, or its own URL for text posts
+    #[serde(default)]
+    pub url: String,
+    /// The post's flair text
+    #[serde(default)]
+    pub link_flair_text: Option<String>,
+    /// The post's flair CSS class
+    #[serde(default)]
+    pub link_flair_css_class: Option<String>,
+    /// Whether the post is marked NSFW
+    #[serde(default, rename = "over_18")]
+    pub nsfw: bool,
+    /// Whether the post is marked as a spoiler
+    #[serde(default)]
+    pub spoiler: bool,
+    /// Whether the post is locked from new comments
+    #[serde(default)]
+    pub locked: bool,
+    /// Whether the post is stickied in its subreddit
+    #[serde(default)]
+    pub stickied: bool,
+    /// The number of comments on the post
+    #[serde(default)]
+    pub num_comments: u64,
+    /// The relative URL of the post's comments
+    #[serde(default)]
+    pub permalink: String,
+    /// The post's embedded media information, if any
+    #[serde(default)]
+    pub media: Option<Value>,
+    /// The post's moderation state
+    #[serde(flatten)]
+    pub moderation: ModerationData,
+}
 
 /// Maps to a single post, either link or text.
-#[derive(Clone)]
-pub struct Post<'a> {
+///
+/// The `A` type parameter is the API client that the struct
+/// was retrieved from, either `Api` or `AsyncApi`.
+pub struct Post<'a, A: ApiClient<'a> = Api> {
     /// Rerefence to the source `Api` struct. Used for calling API endpoints.
-    pub api: &'a Api,
-    /// Post's user.
-    pub user: User<'a>,
-    /// Title's post.
-    pub title: String,
+    pub api: A::Handle,
+    /// Post's data.
+    pub data: PostData,
+}
+
+impl<'a, A: ApiClient<'a>> Clone for Post<'a, A> {
+    fn clone(&self) -> Self {
+        Post {
+            api: self.api.clone(),
+            data: self.data.clone(),
+        }
+    }
+}
+
+impl<'a, A: ApiClient<'a>> Post<'a, A> {
+    /// Create a post struct from a `t3` item in a listing.
+    ///
+    /// # Arguments
+    ///
+    /// * `api` - the API client
+    /// * `item` - the listing item
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let posts = api
+    ///     .query_listing(ql)?
+    ///     .iter()
+    ///     .map(|item| Post::from_listing_item(&api, item))
+    ///     .collect::<Result<Vec<_>, _>>()?;
+    /// ```
+    pub fn from_listing_item<H>(api: H, item: &Value) -> Result<Self, ApiError>
+    where
+        H: ApiHandle<'a, Client = A>,
+        A: ApiClient<'a, Handle = H>,
+    {
+        Ok(Post {
+            api,
+            data: from_thing(item, KIND)?,
+        })
+    }
 
-    /// The post's link, if a link-type post.
-    pub link: Option<String>,
-    /// The post's text, if a text-type post.
-    pub text: Option<String>,
+    /// Get the post's fullname.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let fullname = post.fullname();
+    /// ```
+    pub fn fullname(&self) -> &str {
+        &self.data.fullname
+    }
 }
 
-impl<'a> Post<'a> {}
+impl<'a> Post<'a> {
+    /// Get the tree of comments on the post.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let tree = post.get_comments()?;
+    /// ```
+    pub fn get_comments(&self) -> Result<CommentTree<'a>, ApiError> {
+        self.api.get_comments(&self.data.id)
+    }
+
+    /// Reply to the post with a top-level comment.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - the comment's markdown text
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let comment = post.reply("Hello!")?;
+    /// ```
+    pub fn reply(&self, text: &str) -> Result<Comment<'a>, ApiError> {
+        let item = actions::reply(self.api, self.fullname(), text)?;
+        Comment::from_listing_item(self.api, &item)
+    }
+
+    /// Replace the text of a text post.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - the post's new markdown text
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let post = post.edit("Updated text")?;
+    /// ```
+    pub fn edit(&self, text: &str) -> Result<Post<'a>, ApiError> {
+        let item = actions::edit(self.api, self.fullname(), text)?;
+        Post::from_listing_item(self.api, &item)
+    }
+
+    /// Delete the post.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// post.delete()?;
+    /// ```
+    pub fn delete(&self) -> Result<(), ApiError> {
+        actions::act(self.api, "api/del", self.fullname())
+    }
+
+    /// Save the post, optionally to a category.
+    ///
+    /// # Arguments
+    ///
+    /// * `category` - the category to save the post to (Reddit Gold only)
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// post.save(None)?;
+    /// ```
+    pub fn save(&self, category: Option<&str>) -> Result<(), ApiError> {
+        actions::save(self.api, self.fullname(), category)
+    }
+
+    /// Unsave the post.
+    pub fn unsave(&self) -> Result<(), ApiError> {
+        actions::act(self.api, "api/unsave", self.fullname())
+    }
+
+    /// Hide the post from the logged-in user's listings.
+    pub fn hide(&self) -> Result<(), ApiError> {
+        actions::act(self.api, "api/hide", self.fullname())
+    }
+
+    /// Unhide the post.
+    pub fn unhide(&self) -> Result<(), ApiError> {
+        actions::act(self.api, "api/unhide", self.fullname())
+    }
+
+    /// Vote on the post.
+    ///
+    /// # Arguments
+    ///
+    /// * `direction` - the direction to vote in
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// post.vote(VoteDirection::Up)?;
+    /// ```
+    pub fn vote(&self, direction: VoteDirection) -> Result<(), ApiError> {
+        actions::vote(self.api, self.fullname(), direction)
+    }
+
+    /// Upvote the post.
+    pub fn upvote(&self) -> Result<(), ApiError> {
+        self.vote(VoteDirection::Up)
+    }
+
+    /// Downvote the post.
+    pub fn downvote(&self) -> Result<(), ApiError> {
+        self.vote(VoteDirection::Down)
+    }
+
+    /// Remove the logged-in user's vote on the post.
+    pub fn clear_vote(&self) -> Result<(), ApiError> {
+        self.vote(VoteDirection::Clear)
+    }
+
+    /// Report the post to the subreddit's moderators.
+    ///
+    /// # Arguments
+    ///
+    /// * `reason` - why the post is being reported
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// post.report("Spam")?;
+    /// ```
+    pub fn report(&self, reason: &str) -> Result<(), ApiError> {
+        actions::report(self.api, self.fullname(), reason)
+    }
+
+    /// Mark the post as NSFW.
+    pub fn mark_nsfw(&self) -> Result<(), ApiError> {
+        actions::act(self.api, "api/marknsfw", self.fullname())
+    }
+
+    /// Remove the post's NSFW mark.
+    pub fn unmark_nsfw(&self) -> Result<(), ApiError> {
+        actions::act(self.api, "api/unmarknsfw", self.fullname())
+    }
+
+    /// Mark the post as a spoiler.
+    pub fn mark_spoiler(&self) -> Result<(), ApiError> {
+        actions::act(self.api, "api/spoiler", self.fullname())
+    }
+
+    /// Remove the post's spoiler mark.
+    pub fn unmark_spoiler(&self) -> Result<(), ApiError> {
+        actions::act(self.api, "api/unspoiler", self.fullname())
+    }
+
+    /// Crosspost the post to another subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `subreddit` - name of the subreddit to post to
+    /// * `title` - title of the new post
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let crosspost = post.crosspost("rust_gamedev", "Interesting post")?;
+    /// ```
+    pub fn crosspost(&self, subreddit: &str, title: &str) -> Result<Post<'a>, ApiError> {
+        let mut form_data = HashMap::new();
+        form_data.insert("kind", "crosspost");
+        form_data.insert("sr", subreddit);
+        form_data.insert("title", title);
+        form_data.insert("crosspost_fullname", self.fullname());
+        actions::submit(self.api, form_data)
+    }
+}
 
 #[cfg(test)]
-mod tests {}
+pub(crate) mod tests {
+    use super::Post;
+    use crate::errors::ApiError;
+    use crate::models::comment::tests::sample_comment;
+    use crate::Api;
+    use lazy_static::lazy_static;
+    use mockito::{mock, Matcher};
+    use serde_json::Value;
+
+    lazy_static! {
+        static ref API: Api = Api::new(std::default::Default::default());
+    }
+
+    pub(crate) fn sample_item(id: &str) -> String {
+        format!(
+            "{{\"kind\":\"t3\",\"data\":{{\"id\":\"{0}\",\"name\":\"t3_{0}\",\"author\":\"someone\", \
+             \"subreddit\":\"rust\",\"title\":\"A post\",\"score\":42,\"upvote_ratio\":0.95, \
+             \"created_utc\":1560000000.0,\"is_self\":true,\"selftext\":\"Hello\", \
+             \"url\":\"https://www.reddit.com/r/rust/comments/{0}/a_post/\", \
+             \"link_flair_text\":\"News\",\"link_flair_css_class\":null,\"over_18\":false, \
+             \"spoiler\":true,\"locked\":false,\"stickied\":false,\"num_comments\":7, \
+             \"permalink\":\"/r/rust/comments/{0}/a_post/\",\"media\":null}}}}",
+            id
+        )
+    }
+
+    #[test]
+    fn from_listing_item() {
+        let item: Value = serde_json::from_str(&sample_item("abc")).unwrap();
+        let post = Post::from_listing_item(&*API, &item).unwrap();
+
+        assert_eq!(post.data.id, "abc");
+        assert_eq!(post.fullname(), "t3_abc");
+        assert_eq!(post.data.author, "someone");
+        assert_eq!(post.data.score, 42);
+        assert_eq!(post.data.link_flair_text, Some(String::from("News")));
+        assert!(post.data.spoiler);
+        assert!(!post.data.nsfw);
+        assert_eq!(post.data.num_comments, 7);
+        assert_eq!(post.data.media, None);
+    }
+
+    #[test]
+    fn from_listing_item_wrong_kind() {
+        let item: Value = serde_json::from_str("{\"kind\":\"t1\",\"data\":{}}").unwrap();
+
+        match Post::from_listing_item(&*API, &item) {
+            Err(ApiError::Other(_)) => (),
+            _ => panic!("Expected error"),
+        }
+    }
+
+    pub(crate) fn things_response(things: &[String]) -> String {
+        format!(
+            "{{\"json\":{{\"errors\":[],\"data\":{{\"things\":[{}]}}}}}}",
+            things.join(",")
+        )
+    }
+
+    fn sample_post() -> Post<'static> {
+        let item: Value = serde_json::from_str(&sample_item("abc")).unwrap();
+        Post::from_listing_item(&*API, &item).unwrap()
+    }
+
+    pub(crate) fn form_field(field: &str) -> Matcher {
+        Matcher::Regex(format!("(^|&){}(&|$)", field))
+    }
+
+    #[test]
+    fn reply() {
+        let _m1 = mock("POST", "/api/comment")
+            .match_body(form_field("text=Hello%21"))
+            .with_status(200)
+            .with_body(things_response(&[sample_comment(
+                "ccc", "t3_abc", 0, "\"\"",
+            )]))
+            .create();
+
+        let comment = sample_post().reply("Hello!").unwrap();
+
+        assert_eq!(comment.fullname(), "t1_ccc");
+        assert_eq!(comment.data.parent_id, "t3_abc");
+        _m1.assert();
+    }
+
+    #[test]
+    fn edit() {
+        let _m1 = mock("POST", "/api/editusertext")
+            .match_body(form_field("thing_id=t3_abc"))
+            .with_status(200)
+            .with_body(things_response(&[sample_item("abc")]))
+            .create();
+
+        let post = sample_post().edit("Updated").unwrap();
+
+        assert_eq!(post.fullname(), "t3_abc");
+        _m1.assert();
+    }
+
+    #[test]
+    fn simple_actions() {
+        let post = sample_post();
+        let mocks: Vec<_> = [
+            "del",
+            "unsave",
+            "hide",
+            "unhide",
+            "marknsfw",
+            "unmarknsfw",
+            "spoiler",
+            "unspoiler",
+        ]
+        .iter()
+        .map(|endpoint| {
+            mock("POST", format!("/api/{}", endpoint).as_str())
+                .match_body(form_field("id=t3_abc"))
+                .with_status(200)
+                .with_body("{}")
+                .create()
+        })
+        .collect();
+
+        post.delete().unwrap();
+        post.unsave().unwrap();
+        post.hide().unwrap();
+        post.unhide().unwrap();
+        post.mark_nsfw().unwrap();
+        post.unmark_nsfw().unwrap();
+        post.mark_spoiler().unwrap();
+        post.unmark_spoiler().unwrap();
+
+        for m in mocks {
+            m.assert();
+        }
+    }
+
+    #[test]
+    fn vote() {
+        let _m1 = mock("POST", "/api/vote")
+            .match_body(form_field("dir=-1"))
+            .with_status(200)
+            .with_body("{}")
+            .create();
+
+        sample_post().downvote().unwrap();
+
+        _m1.assert();
+    }
+
+    #[test]
+    fn save_and_report() {
+        let _m1 = mock("POST", "/api/save")
+            .match_body(form_field("category=bots"))
+            .with_status(200)
+            .with_body("{}")
+            .create();
+        let _m2 = mock("POST", "/api/report")
+            .match_body(form_field("reason=Spam"))
+            .with_status(200)
+            .with_body("{\"json\":{\"errors\":[[\"SUBREDDIT_NOEXIST\",\"no\",\"sr\"]]}}")
+            .create();
+        let post = sample_post();
+
+        post.save(Some("bots")).unwrap();
+        match post.report("Spam") {
+            Err(ApiError::Reddit(errors)) => assert_eq!(errors[0].code, "SUBREDDIT_NOEXIST"),
+            _ => panic!("Expected Reddit error"),
+        }
+        _m1.assert();
+        _m2.assert();
+    }
+
+    #[test]
+    fn crosspost() {
+        let _m1 = mock("POST", "/api/submit")
+            .match_body(form_field("crosspost_fullname=t3_abc"))
+            .with_status(200)
+            .with_body("{\"json\":{\"errors\":[],\"data\":{\"id\":\"xyz\",\"name\":\"t3_xyz\"}}}")
+            .create();
+        let _m2 = mock("GET", "/by_id/t3_xyz")
+            .with_status(200)
+            .with_body(format!(
+                "{{\"kind\":\"Listing\",\"data\":{{\"children\":[{}]}}}}",
+                sample_item("xyz")
+            ))
+            .create();
+
+        let post = sample_post()
+            .crosspost("rust_gamedev", "Interesting")
+            .unwrap();
+
+        assert_eq!(post.data.id, "xyz");
+        _m1.assert();
+        _m2.assert();
+    }
+}
@@ -0,0 +1,260 @@
+//! Streams of new items from listing endpoints.
+//!
+//! A stream polls a listing for new items, yielding each one
+//! exactly once, oldest first. Polling slows down while nothing
+//! new comes in and speeds back up once items arrive.
+//!
+//! # Examples
+//!
+//! ```rust,no_run,ignore
+//! let options = StreamOptions::new().skip_existing(true);
+//! for post in api.get_subreddit("rust")?.stream_submissions(options) {
+//!     println!("{}", post?.data.title);
+//! }
+//! ```
+
+use crate::query_listing::QueryListingRequest;
+use crate::{Api, ApiError};
+use serde_json::Value;
+use std::collections::{HashSet, VecDeque};
+use std::thread;
+use std::time::Duration;
+
+/// Builder struct for the options used when streaming items.
+#[derive(Clone, Debug)]
+pub struct StreamOptions {
+    /// Whether to skip the items that already exist when the stream starts
+    pub skip_existing: bool,
+    /// The number of items to request per poll, at most 100
+    pub limit: u64,
+    /// The number of seen item fullnames to remember, which
+    /// should be well over `limit` and is never less than it
+    pub cache_size: usize,
+    /// The time to wait between polls while items are coming in
+    pub min_delay: Duration,
+    /// The longest time to wait between polls while nothing new comes in
+    pub max_delay: Duration,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        StreamOptions::new()
+    }
+}
+
+impl StreamOptions {
+    /// Construct a new builder.
+    pub fn new() -> Self {
+        StreamOptions {
+            skip_existing: false,
+            limit: 100,
+            cache_size: 300,
+            min_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(16),
+        }
+    }
+
+    /// Override the `skip_existing` field.
+    pub fn skip_existing(mut self, skip_existing: bool) -> Self {
+        self.skip_existing = skip_existing;
+        self
+    }
+
+    /// Override the `limit` field, raising `cache_size` to
+    /// match if it's smaller.
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = limit;
+        self.cache_size = self.cache_size.max(limit as usize);
+        self
+    }
+
+    /// Override the `cache_size` field, keeping it at least `limit`.
+    pub fn cache_size(mut self, cache_size: usize) -> Self {
+        self.cache_size = cache_size.max(self.limit as usize);
+        self
+    }
+
+    /// Override the `min_delay` field.
+    pub fn min_delay(mut self, min_delay: Duration) -> Self {
+        self.min_delay = min_delay;
+        self
+    }
+
+    /// Override the `max_delay` field.
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+}
+
+/// A bounded set of fullnames, forgetting the oldest once full.
+#[derive(Debug)]
+struct SeenCache {
+    capacity: usize,
+    order: VecDeque<String>,
+    ids: HashSet<String>,
+}
+
+impl SeenCache {
+    fn new(capacity: usize) -> Self {
+        SeenCache {
+            capacity,
+            order: VecDeque::new(),
+            ids: HashSet::new(),
+        }
+    }
+
+    /// Add the fullname, returning whether it wasn't already present.
+    fn insert(&mut self, fullname: &str) -> bool {
+        if self.ids.contains(fullname) {
+            return false;
+        }
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+        self.order.push_back(fullname.to_owned());
+        self.ids.insert(fullname.to_owned());
+        true
+    }
+}
+
+/// Iterator over new items from a listing endpoint.
+///
+/// Never ends on its own, blocking between polls; use `take` and
+/// friends to stop. Errors are yielded as they occur, with the
+/// stream continuing to poll afterwards.
+pub struct Stream<'a, T> {
+    api: &'a Api,
+    path: String,
+    options: StreamOptions,
+    parse: fn(&'a Api, &Value) -> Result<T, ApiError>,
+    seen: SeenCache,
+    pending: VecDeque<Value>,
+    delay: Duration,
+    polled: bool,
+    waiting: bool,
+}
+
+impl<'a, T> Stream<'a, T> {
+    /// Create a stream of the items from a listing endpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `api` - the API client
+    /// * `path` - the listing's relative URL path, i.e. "r/rust/new"
+    /// * `options` - the stream's options
+    /// * `parse` - function to convert a listing item into the yielded type
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let stream = Stream::new(&api, "r/rust/new", StreamOptions::new(), Post::from_listing_item);
+    /// ```
+    pub fn new(
+        api: &'a Api,
+        path: &str,
+        options: StreamOptions,
+        parse: fn(&'a Api, &Value) -> Result<T, ApiError>,
+    ) -> Self {
+        Stream {
+            api,
+            path: path.to_owned(),
+            // a smaller cache would forget items from the page
+            // being read, and yield them again on the next poll
+            seen: SeenCache::new(options.cache_size.max(options.limit as usize)),
+            delay: options.min_delay,
+            options,
+            parse,
+            pending: VecDeque::new(),
+            polled: false,
+            waiting: false,
+        }
+    }
+
+    /// Poll the listing once, queueing any items not seen before.
+    pub(crate) fn poll(&mut self) -> Result<(), ApiError> {
+        let ql = QueryListingRequest::new(&self.path, self.options.limit, 1);
+        let (items, _) = self.api.get_listing_page(&ql, &ql.paging())?;
+        let skip = self.options.skip_existing && !self.polled;
+        self.polled = true;
+
+        let mut found = false;
+        // listings are newest first, so queue from the end
+        for item in items.into_iter().rev() {
+            let fullname = item["data"]["name"].as_str().unwrap_or_default();
+            if self.seen.insert(fullname) && !skip {
+                self.pending.push_back(item);
+                found = true;
+            }
+        }
+        self.delay = if found {
+            self.options.min_delay
+        } else {
+            (self.delay * 2).min(self.options.max_delay)
+        };
+        Ok(())
+    }
+}
+
+impl<'a, T> Iterator for Stream<'a, T> {
+    type Item = Result<T, ApiError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                return Some((self.parse)(self.api, &item));
+            }
+            if self.waiting {
+                thread::sleep(self.delay);
+            }
+            self.waiting = true;
+            if let Err(e) = self.poll() {
+                self.delay = (self.delay * 2).min(self.options.max_delay);
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SeenCache, Stream, StreamOptions};
+    use crate::models::post::Post;
+    use crate::Api;
+
+    #[test]
+    fn seen_cache() {
+        let mut seen = SeenCache::new(2);
+
+        assert!(seen.insert("t3_a"));
+        assert!(!seen.insert("t3_a"));
+        assert!(seen.insert("t3_b"));
+        assert!(seen.insert("t3_c"));
+        // "t3_a" was forgotten to make room for "t3_c"
+        assert!(seen.insert("t3_a"));
+        assert!(!seen.insert("t3_c"));
+    }
+
+    #[test]
+    fn cache_size_covers_limit() {
+        assert_eq!(StreamOptions::new().limit(50).cache_size(10).cache_size, 50);
+        assert_eq!(
+            StreamOptions::new()
+                .limit(10)
+                .cache_size(20)
+                .limit(50)
+                .cache_size,
+            50
+        );
+        assert_eq!(StreamOptions::new().limit(50).cache_size(80).cache_size, 80);
+
+        let mut options = StreamOptions::new();
+        options.cache_size = 10;
+        let api = Api::new(std::default::Default::default());
+        let stream = Stream::new(&api, "r/rust/new", options, Post::from_listing_item);
+
+        assert_eq!(stream.seen.capacity, 100);
+    }
+}
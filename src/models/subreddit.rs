@@ -6,41 +6,1418 @@
 //! let subreddit = api.get_subreddit("name")?;
 //! ```
 
-use crate::{Api, ApiError, QueryListingRequest, Value};
+#[cfg(feature = "async")]
+use crate::async_api::{ApiFuture, AsyncApi};
+use crate::models::actions;
+use crate::models::comment::Comment;
+use crate::models::moderation::{BanOptions, BannedUser, ModAction, Moderator, Relationship};
+use crate::models::post::Post;
+use crate::models::stream::{Stream, StreamOptions};
+use crate::models::{ApiClient, Item};
+use crate::query_listing::TimeFilter;
+use crate::{parse_json, Api, ApiError, QueryListingRequest};
+#[cfg(feature = "async")]
+use futures::Future;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
 
 /// Maps to a single subreddit.
-#[derive(Clone)]
-pub struct Subreddit<'a> {
+///
+/// The `A` type parameter is the API client that the struct
+/// was retrieved from, either `Api` or `AsyncApi`.
+pub struct Subreddit<'a, A: ApiClient<'a> = Api> {
     /// Rerefence to the source `Api` struct. Used for calling API endpoints.
-    pub api: &'a Api,
+    pub api: A::Handle,
     /// Name of the subreddit.
     pub name: String,
 }
 
+impl<'a, A: ApiClient<'a>> Clone for Subreddit<'a, A> {
+    fn clone(&self) -> Self {
+        Subreddit {
+            api: self.api.clone(),
+            name: self.name.clone(),
+        }
+    }
+}
+
+/// Builder struct for the options used when submitting posts.
+///
+/// # Examples
+///
+/// ```rust,no_run,ignore
+/// let options = SubmitOptions::new()
+///     .flair_text(Some("Announcement"))
+///     .send_replies(false);
+/// let post = subreddit.submit_text("Title", "Text", options)?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct SubmitOptions<'a> {
+    /// The id of the flair template to apply
+    pub flair_id: Option<&'a str>,
+    /// The flair text to apply
+    pub flair_text: Option<&'a str>,
+    /// Whether to mark the post as NSFW
+    pub nsfw: bool,
+    /// Whether to mark the post as a spoiler
+    pub spoiler: bool,
+    /// Whether to send comment replies to the author's inbox
+    pub send_replies: bool,
+    /// Whether to submit a link that has already been submitted
+    pub resubmit: bool,
+}
+
+impl<'a> Default for SubmitOptions<'a> {
+    fn default() -> Self {
+        SubmitOptions::new()
+    }
+}
+
+impl<'a> SubmitOptions<'a> {
+    /// Construct a new builder.
+    pub fn new() -> Self {
+        SubmitOptions {
+            flair_id: None,
+            flair_text: None,
+            nsfw: false,
+            spoiler: false,
+            send_replies: true,
+            resubmit: false,
+        }
+    }
+
+    /// Override the `flair_id` field.
+    pub fn flair_id(mut self, flair_id: Option<&'a str>) -> Self {
+        self.flair_id = flair_id;
+        self
+    }
+
+    /// Override the `flair_text` field.
+    pub fn flair_text(mut self, flair_text: Option<&'a str>) -> Self {
+        self.flair_text = flair_text;
+        self
+    }
+
+    /// Override the `nsfw` field.
+    pub fn nsfw(mut self, nsfw: bool) -> Self {
+        self.nsfw = nsfw;
+        self
+    }
+
+    /// Override the `spoiler` field.
+    pub fn spoiler(mut self, spoiler: bool) -> Self {
+        self.spoiler = spoiler;
+        self
+    }
+
+    /// Override the `send_replies` field.
+    pub fn send_replies(mut self, send_replies: bool) -> Self {
+        self.send_replies = send_replies;
+        self
+    }
+
+    /// Override the `resubmit` field.
+    pub fn resubmit(mut self, resubmit: bool) -> Self {
+        self.resubmit = resubmit;
+        self
+    }
+
+    /// The form data for the options.
+    fn form_data(&self) -> HashMap<&'a str, &'a str> {
+        let flag = |value: bool| if value { "true" } else { "false" };
+        let mut form_data = HashMap::new();
+        if let Some(flair_id) = self.flair_id {
+            form_data.insert("flair_id", flair_id);
+        }
+        if let Some(flair_text) = self.flair_text {
+            form_data.insert("flair_text", flair_text);
+        }
+        form_data.insert("nsfw", flag(self.nsfw));
+        form_data.insert("spoiler", flag(self.spoiler));
+        form_data.insert("sendreplies", flag(self.send_replies));
+        form_data.insert("resubmit", flag(self.resubmit));
+        form_data
+    }
+}
+
+/// The query parameters for a listing's time filter.
+fn time_params(time: Option<TimeFilter>) -> Vec<(&'static str, &'static str)> {
+    match time {
+        Some(time) => vec![("t", time.as_str())],
+        None => Vec::new(),
+    }
+}
+
+/// Convert listing items into posts.
+#[cfg(feature = "async")]
+fn to_posts(api: &AsyncApi, items: &[Value]) -> Result<Vec<Post<'static, AsyncApi>>, ApiError> {
+    items
+        .iter()
+        .map(|item| Post::from_listing_item(api.clone(), item))
+        .collect()
+}
+
 impl<'a> Subreddit<'a> {
-    /// Get the top `count` posts from the subreddit.
+    /// Get `count` posts from one of the subreddit's listings.
+    fn get_listing(
+        &self,
+        sort: &str,
+        count: u64,
+        time: Option<TimeFilter>,
+    ) -> Result<Vec<Post<'a>>, ApiError> {
+        let path = format!("r/{}/{}", self.name, sort);
+        let params = time_params(time);
+        let ql = QueryListingRequest::new(&path, count.min(100), 0).params(&params);
+        self.api
+            .listing_iter(ql)
+            .take(count as usize)
+            .map(|item| Post::from_listing_item(self.api, &item?))
+            .collect()
+    }
+
+    /// Get the `count` "hot" posts from the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of posts to retrieve
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let posts = subreddit.get_hot(25)?;
+    /// ```
+    pub fn get_hot(&self, count: u64) -> Result<Vec<Post<'a>>, ApiError> {
+        self.get_listing("hot", count, None)
+    }
+
+    /// Get the `count` newest posts from the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of posts to retrieve
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let posts = subreddit.get_new(25)?;
+    /// ```
+    pub fn get_new(&self, count: u64) -> Result<Vec<Post<'a>>, ApiError> {
+        self.get_listing("new", count, None)
+    }
+
+    /// Get the `count` "rising" posts from the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of posts to retrieve
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let posts = subreddit.get_rising(25)?;
+    /// ```
+    pub fn get_rising(&self, count: u64) -> Result<Vec<Post<'a>>, ApiError> {
+        self.get_listing("rising", count, None)
+    }
+
+    /// Get the `count` most controversial posts from the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of posts to retrieve
+    /// * `time` - the time window to get posts from, or `None` for Reddit's default
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let posts = subreddit.get_controversial(25, Some(TimeFilter::Week))?;
+    /// ```
+    pub fn get_controversial(
+        &self,
+        count: u64,
+        time: Option<TimeFilter>,
+    ) -> Result<Vec<Post<'a>>, ApiError> {
+        self.get_listing("controversial", count, time)
+    }
+
+    /// Get the `count` top posts from the subreddit.
     ///
     /// # Arguments
     ///
     /// * `count` - number of posts to retrieve
+    /// * `time` - the time window to get posts from, or `None` for Reddit's default
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let posts = subreddit.get_top(25, Some(TimeFilter::Week))?;
+    /// ```
+    pub fn get_top(&self, count: u64, time: Option<TimeFilter>) -> Result<Vec<Post<'a>>, ApiError> {
+        self.get_listing("top", count, time)
+    }
+
+    /// Get the `count` most recently gilded posts from the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of posts to retrieve
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let posts = subreddit.get_gilded(25)?;
+    /// ```
+    pub fn get_gilded(&self, count: u64) -> Result<Vec<Post<'a>>, ApiError> {
+        self.get_listing("gilded", count, None)
+    }
+
+    /// Get `count` items from one of the subreddit's moderation queues.
+    fn get_mod_queue(&self, queue: &str, count: u64) -> Result<Vec<Item<'a>>, ApiError> {
+        let path = format!("r/{}/about/{}", self.name, queue);
+        let ql = QueryListingRequest::new(&path, count.min(100), 0);
+        self.api
+            .listing_iter(ql)
+            .take(count as usize)
+            .map(|item| Item::from_listing_item(self.api, &item?))
+            .collect()
+    }
+
+    /// Get `count` items that are waiting for a moderator's review,
+    /// either from reports or from the spam filter.
+    ///
+    /// Requires the account to moderate the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of items to retrieve
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// for item in subreddit.get_modqueue(25)? {
+    ///     item.approve()?;
+    /// }
+    /// ```
+    pub fn get_modqueue(&self, count: u64) -> Result<Vec<Item<'a>>, ApiError> {
+        self.get_mod_queue("modqueue", count)
+    }
+
+    /// Get `count` reported items.
+    ///
+    /// Requires the account to moderate the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of items to retrieve
     ///
     /// # Examples
     ///
     /// ```rust,no_run,ignore
-    /// let posts = subreddit.get_top(25)?;
+    /// let items = subreddit.get_reports(25)?;
     /// ```
-    pub fn get_top(&self, count: u64) -> Result<Vec<Value>, ApiError> {
-        let (mp, times) = if count > 100 {
-            (100, count / 100)
+    pub fn get_reports(&self, count: u64) -> Result<Vec<Item<'a>>, ApiError> {
+        self.get_mod_queue("reports", count)
+    }
+
+    /// Get `count` items that were removed as spam.
+    ///
+    /// Requires the account to moderate the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of items to retrieve
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let items = subreddit.get_spam(25)?;
+    /// ```
+    pub fn get_spam(&self, count: u64) -> Result<Vec<Item<'a>>, ApiError> {
+        self.get_mod_queue("spam", count)
+    }
+
+    /// Get `count` recently edited items.
+    ///
+    /// Requires the account to moderate the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of items to retrieve
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let items = subreddit.get_edited(25)?;
+    /// ```
+    pub fn get_edited(&self, count: u64) -> Result<Vec<Item<'a>>, ApiError> {
+        self.get_mod_queue("edited", count)
+    }
+
+    /// Get `count` posts that no moderator has approved or removed yet.
+    ///
+    /// Requires the account to moderate the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of posts to retrieve
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let posts = subreddit.get_unmoderated(25)?;
+    /// ```
+    pub fn get_unmoderated(&self, count: u64) -> Result<Vec<Post<'a>>, ApiError> {
+        self.get_listing("about/unmoderated", count, None)
+    }
+
+    /// Get the `count` newest entries from the subreddit's moderation log.
+    ///
+    /// Requires the account to moderate the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of entries to retrieve
+    /// * `moderator` - only get the actions of the moderator with this username
+    /// * `action` - only get actions of this type, i.e. "removelink"
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// for entry in subreddit.get_mod_log(100, Some("some-mod"), Some("banuser"))? {
+    ///     println!("{} banned {:?}", entry.moderator, entry.target_author);
+    /// }
+    /// ```
+    pub fn get_mod_log(
+        &self,
+        count: u64,
+        moderator: Option<&str>,
+        action: Option<&str>,
+    ) -> Result<Vec<ModAction>, ApiError> {
+        let path = format!("r/{}/about/log", self.name);
+        let mut params = Vec::new();
+        if let Some(moderator) = moderator {
+            params.push(("mod", moderator));
+        }
+        if let Some(action) = action {
+            params.push(("type", action));
+        }
+        let ql = QueryListingRequest::new(&path, count.min(100), 0).params(&params);
+        self.api
+            .listing_iter(ql)
+            .take(count as usize)
+            .map(|item| ModAction::from_listing_item(&item?))
+            .collect()
+    }
+
+    /// Get `count` users from one of the subreddit's relationship listings.
+    fn get_relationships<T: DeserializeOwned>(
+        &self,
+        which: &str,
+        count: u64,
+    ) -> Result<Vec<T>, ApiError> {
+        let path = format!("r/{}/about/{}", self.name, which);
+        let ql = QueryListingRequest::new(&path, count.min(100), 0);
+        self.api
+            .listing_iter(ql)
+            .take(count as usize)
+            .map(|item| Ok(serde_json::from_value(item?)?))
+            .collect()
+    }
+
+    /// Get `count` of the users banned from the subreddit.
+    ///
+    /// Requires the account to moderate the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of users to retrieve
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let banned = subreddit.get_banned(100)?;
+    /// ```
+    pub fn get_banned(&self, count: u64) -> Result<Vec<BannedUser>, ApiError> {
+        self.get_relationships("banned", count)
+    }
+
+    /// Get `count` of the users muted in the subreddit's modmail.
+    ///
+    /// Requires the account to moderate the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of users to retrieve
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let muted = subreddit.get_muted(100)?;
+    /// ```
+    pub fn get_muted(&self, count: u64) -> Result<Vec<Relationship>, ApiError> {
+        self.get_relationships("muted", count)
+    }
+
+    /// Get `count` of the subreddit's approved submitters.
+    ///
+    /// Requires the account to moderate the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of users to retrieve
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let contributors = subreddit.get_contributors(100)?;
+    /// ```
+    pub fn get_contributors(&self, count: u64) -> Result<Vec<Relationship>, ApiError> {
+        self.get_relationships("contributors", count)
+    }
+
+    /// Get the subreddit's moderators.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// for moderator in subreddit.get_moderators()? {
+    ///     println!("{}: {:?}", moderator.name, moderator.permissions);
+    /// }
+    /// ```
+    pub fn get_moderators(&self) -> Result<Vec<Moderator>, ApiError> {
+        // the moderators aren't paginated, so they all come in one response
+        let path = format!("r/{}/about/moderators", self.name);
+        let mut resp = self.api.query("GET", &path, None, None)?;
+        let data: Value = parse_json(&mut resp)?;
+        Ok(serde_json::from_value(data["data"]["children"].clone())?)
+    }
+
+    /// Add a relationship of the `kind` between the user and the subreddit.
+    fn friend<'b>(
+        &self,
+        name: &'b str,
+        kind: &'b str,
+        mut form_data: HashMap<&'b str, &'b str>,
+    ) -> Result<(), ApiError> {
+        form_data.insert("name", name);
+        form_data.insert("type", kind);
+        let path = format!("r/{}/api/friend", self.name);
+        self.api.post_json(&path, form_data)?;
+        Ok(())
+    }
+
+    /// Remove a relationship of the `kind` between the user and the subreddit.
+    fn unfriend(&self, name: &str, kind: &str) -> Result<(), ApiError> {
+        let mut form_data = HashMap::new();
+        form_data.insert("name", name);
+        form_data.insert("type", kind);
+        let path = format!("r/{}/api/unfriend", self.name);
+        self.api.post_json(&path, form_data)?;
+        Ok(())
+    }
+
+    /// Ban a user from the subreddit.
+    ///
+    /// Banning a user that's already banned updates their ban.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the user's name
+    /// * `options` - the ban's options
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let options = BanOptions::new()
+    ///     .duration(Some(7))
+    ///     .reason(Some("Spam"))
+    ///     .message(Some("Please read the rules."));
+    /// subreddit.ban("some-user", options)?;
+    /// ```
+    pub fn ban(&self, name: &str, options: BanOptions) -> Result<(), ApiError> {
+        let duration = options.duration.map(|days| days.to_string());
+        let mut form_data = HashMap::new();
+        if let Some(duration) = &duration {
+            form_data.insert("duration", duration.as_str());
+        }
+        if let Some(reason) = options.reason {
+            form_data.insert("ban_reason", reason);
+        }
+        if let Some(note) = options.note {
+            form_data.insert("note", note);
+        }
+        if let Some(message) = options.message {
+            form_data.insert("ban_message", message);
+        }
+        self.friend(name, "banned", form_data)
+    }
+
+    /// Unban a user from the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the user's name
+    pub fn unban(&self, name: &str) -> Result<(), ApiError> {
+        self.unfriend(name, "banned")
+    }
+
+    /// Mute a user, preventing them from messaging the subreddit's moderators.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the user's name
+    pub fn mute(&self, name: &str) -> Result<(), ApiError> {
+        self.friend(name, "muted", HashMap::new())
+    }
+
+    /// Unmute a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the user's name
+    pub fn unmute(&self, name: &str) -> Result<(), ApiError> {
+        self.unfriend(name, "muted")
+    }
+
+    /// Add a user to the subreddit's approved submitters.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the user's name
+    pub fn add_contributor(&self, name: &str) -> Result<(), ApiError> {
+        self.friend(name, "contributor", HashMap::new())
+    }
+
+    /// Remove a user from the subreddit's approved submitters.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the user's name
+    pub fn remove_contributor(&self, name: &str) -> Result<(), ApiError> {
+        self.unfriend(name, "contributor")
+    }
+
+    /// Allow a user to edit the subreddit's wiki.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the user's name
+    pub fn add_wiki_contributor(&self, name: &str) -> Result<(), ApiError> {
+        self.friend(name, "wikicontributor", HashMap::new())
+    }
+
+    /// Stop allowing a user to edit the subreddit's wiki.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the user's name
+    pub fn remove_wiki_contributor(&self, name: &str) -> Result<(), ApiError> {
+        self.unfriend(name, "wikicontributor")
+    }
+
+    /// Invite a user to moderate the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the user's name
+    /// * `permissions` - the permissions to give, i.e. "posts" or "wiki",
+    ///   or none for full permissions
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// subreddit.invite_moderator("some-user", &["posts", "mail"])?;
+    /// ```
+    pub fn invite_moderator(&self, name: &str, permissions: &[&str]) -> Result<(), ApiError> {
+        let permissions = if permissions.is_empty() {
+            String::from("+all")
         } else {
-            (count, 1)
+            permissions
+                .iter()
+                .map(|permission| format!("+{}", permission))
+                .collect::<Vec<_>>()
+                .join(",")
         };
-        let path = format!("r/{}/top", self.name);
-        let ql = QueryListingRequest::new(&path, mp, times);
-        let posts = self.api.query_listing(ql)?;
-        Ok(posts.iter().take(count as usize).cloned().collect())
+        let mut form_data = HashMap::new();
+        form_data.insert("permissions", permissions.as_str());
+        self.friend(name, "moderator_invite", form_data)
+    }
+
+    /// Revoke a user's pending invitation to moderate the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the user's name
+    pub fn revoke_moderator_invite(&self, name: &str) -> Result<(), ApiError> {
+        self.unfriend(name, "moderator_invite")
+    }
+
+    /// Remove a moderator from the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the moderator's username
+    pub fn remove_moderator(&self, name: &str) -> Result<(), ApiError> {
+        self.unfriend(name, "moderator")
+    }
+
+    /// Stream the subreddit's new posts as they are submitted.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - the stream's options
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// for post in subreddit.stream_submissions(StreamOptions::new().skip_existing(true)) {
+    ///     println!("{}", post?.data.title);
+    /// }
+    /// ```
+    pub fn stream_submissions(&self, options: StreamOptions) -> Stream<'a, Post<'a>> {
+        let path = format!("r/{}/new", self.name);
+        Stream::new(self.api, &path, options, Post::from_listing_item)
+    }
+
+    /// Stream the subreddit's new comments as they are made.
+    ///
+    /// The streamed comments don't include their replies.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - the stream's options
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// for comment in subreddit.stream_comments(StreamOptions::new()) {
+    ///     println!("{}", comment?.data.body);
+    /// }
+    /// ```
+    pub fn stream_comments(&self, options: StreamOptions) -> Stream<'a, Comment<'a>> {
+        let path = format!("r/{}/comments", self.name);
+        Stream::new(self.api, &path, options, Comment::from_listing_item)
+    }
+
+    /// Submit a post to the subreddit.
+    fn submit<'b>(
+        &self,
+        kind: &'b str,
+        title: &'b str,
+        content: (&'b str, &'b str),
+        options: SubmitOptions<'b>,
+    ) -> Result<Post<'a>, ApiError> {
+        let mut form_data = options.form_data();
+        form_data.insert("sr", &self.name);
+        form_data.insert("kind", kind);
+        form_data.insert("title", title);
+        form_data.insert(content.0, content.1);
+        actions::submit(self.api, form_data)
+    }
+
+    /// Submit a text post to the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `title` - the post's title
+    /// * `text` - the post's markdown text
+    /// * `options` - the post's options
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let post = subreddit.submit_text("Title", "Text", SubmitOptions::new())?;
+    /// ```
+    pub fn submit_text(
+        &self,
+        title: &str,
+        text: &str,
+        options: SubmitOptions,
+    ) -> Result<Post<'a>, ApiError> {
+        self.submit("self", title, ("text", text), options)
+    }
+
+    /// Submit a link post to the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `title` - the post's title
+    /// * `url` - the URL to link to
+    /// * `options` - the post's options
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let post = subreddit.submit_link(
+    ///     "Title",
+    ///     "https://www.rust-lang.org",
+    ///     SubmitOptions::new().resubmit(true),
+    /// )?;
+    /// ```
+    pub fn submit_link(
+        &self,
+        title: &str,
+        url: &str,
+        options: SubmitOptions,
+    ) -> Result<Post<'a>, ApiError> {
+        self.submit("link", title, ("url", url), options)
+    }
+
+    /// Crosspost a post to the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `post` - the post to crosspost
+    /// * `title` - the new post's title
+    /// * `options` - the new post's options
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let post = subreddit.submit_crosspost(&original, "Title", SubmitOptions::new())?;
+    /// ```
+    pub fn submit_crosspost(
+        &self,
+        post: &Post,
+        title: &str,
+        options: SubmitOptions,
+    ) -> Result<Post<'a>, ApiError> {
+        let fullname = post.fullname();
+        self.submit(
+            "crosspost",
+            title,
+            ("crosspost_fullname", fullname),
+            options,
+        )
+    }
+}
+
+#[cfg(feature = "async")]
+impl Subreddit<'static, AsyncApi> {
+    /// Get `count` posts from one of the subreddit's listings.
+    fn get_listing(
+        &self,
+        sort: &str,
+        count: u64,
+        time: Option<TimeFilter>,
+    ) -> ApiFuture<'static, Vec<Post<'static, AsyncApi>>> {
+        let path = format!("r/{}/{}", self.name, sort);
+        let params = time_params(time);
+        let ql = QueryListingRequest::new(&path, count.min(100), 0).params(&params);
+        let api = self.api.clone();
+        Box::new(
+            self.api
+                .listing_take(ql, count)
+                .and_then(move |posts| to_posts(&api, &posts)),
+        )
+    }
+
+    /// Get the `count` "hot" posts from the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of posts to retrieve
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let posts = subreddit.get_hot(25).wait()?;
+    /// ```
+    pub fn get_hot(&self, count: u64) -> ApiFuture<'static, Vec<Post<'static, AsyncApi>>> {
+        self.get_listing("hot", count, None)
+    }
+
+    /// Get the `count` newest posts from the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of posts to retrieve
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let posts = subreddit.get_new(25).wait()?;
+    /// ```
+    pub fn get_new(&self, count: u64) -> ApiFuture<'static, Vec<Post<'static, AsyncApi>>> {
+        self.get_listing("new", count, None)
+    }
+
+    /// Get the `count` "rising" posts from the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of posts to retrieve
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let posts = subreddit.get_rising(25).wait()?;
+    /// ```
+    pub fn get_rising(&self, count: u64) -> ApiFuture<'static, Vec<Post<'static, AsyncApi>>> {
+        self.get_listing("rising", count, None)
+    }
+
+    /// Get the `count` most controversial posts from the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of posts to retrieve
+    /// * `time` - the time window to get posts from, or `None` for Reddit's default
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let posts = subreddit.get_controversial(25, Some(TimeFilter::Week)).wait()?;
+    /// ```
+    pub fn get_controversial(
+        &self,
+        count: u64,
+        time: Option<TimeFilter>,
+    ) -> ApiFuture<'static, Vec<Post<'static, AsyncApi>>> {
+        self.get_listing("controversial", count, time)
+    }
+
+    /// Get the `count` top posts from the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of posts to retrieve
+    /// * `time` - the time window to get posts from, or `None` for Reddit's default
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let posts = subreddit.get_top(25, Some(TimeFilter::Week)).wait()?;
+    /// ```
+    pub fn get_top(
+        &self,
+        count: u64,
+        time: Option<TimeFilter>,
+    ) -> ApiFuture<'static, Vec<Post<'static, AsyncApi>>> {
+        self.get_listing("top", count, time)
+    }
+
+    /// Get the `count` most recently gilded posts from the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of posts to retrieve
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let posts = subreddit.get_gilded(25).wait()?;
+    /// ```
+    pub fn get_gilded(&self, count: u64) -> ApiFuture<'static, Vec<Post<'static, AsyncApi>>> {
+        self.get_listing("gilded", count, None)
     }
 }
 
 #[cfg(test)]
-mod tests {}
+pub(crate) mod tests {
+    use super::{SubmitOptions, Subreddit};
+    #[cfg(feature = "async")]
+    use crate::async_api::AsyncApi;
+    use crate::errors::ApiError;
+    use crate::models::comment::tests::{listing, sample_comment};
+    use crate::models::moderation::BanOptions;
+    use crate::models::post::tests::{form_field, sample_item};
+    use crate::models::post::Post;
+    use crate::models::stream::StreamOptions;
+    use crate::models::Item;
+    use crate::query_listing::TimeFilter;
+    use crate::Api;
+    use mockito::mock;
+    use serde_json::Value;
+    use std::time::Duration;
+    #[cfg(feature = "async")]
+    use tokio::runtime::current_thread::Runtime;
+
+    pub(crate) fn sample_subreddit<'a>(api: &'a Api, name: &str) -> Subreddit<'a> {
+        Subreddit {
+            api,
+            name: name.to_owned(),
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn get_hot_async() {
+        let page = |after: &str, ids: &[&str]| {
+            let items: Vec<String> = ids.iter().map(|id| sample_item(id)).collect();
+            format!(
+                "{{\"data\":{{\"after\":{},\"children\":[{}]}}}}",
+                after,
+                items.join(",")
+            )
+        };
+        let _m1 = mock("GET", "/r/rust/hot?limit=3&show=all")
+            .with_status(200)
+            .with_body(page("\"t3_bbbbb\"", &["aaaaa", "bbbbb"]))
+            .create();
+        let _m2 = mock("GET", "/r/rust/hot?limit=3&after=t3_bbbbb&count=2&show=all")
+            .with_status(200)
+            .with_body(page("\"t3_ddddd\"", &["ccccc", "ddddd"]))
+            .create();
+        let subreddit: Subreddit<'static, AsyncApi> = Subreddit {
+            api: AsyncApi::new(std::default::Default::default()),
+            name: String::from("rust"),
+        };
+
+        let posts = Runtime::new()
+            .unwrap()
+            .block_on(subreddit.get_hot(3))
+            .unwrap();
+
+        let ids: Vec<&str> = posts.iter().map(|p| p.data.id.as_str()).collect();
+        assert_eq!(ids, vec!["aaaaa", "bbbbb", "ccccc"]);
+        _m1.assert();
+        _m2.assert();
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn get_new_async_runs_out() {
+        let _m1 = mock("GET", "/r/rust/new?limit=5&show=all")
+            .with_status(200)
+            .with_body(format!(
+                "{{\"data\":{{\"after\":null,\"children\":[{}]}}}}",
+                sample_item("aaaaa")
+            ))
+            .create();
+        let subreddit: Subreddit<'static, AsyncApi> = Subreddit {
+            api: AsyncApi::new(std::default::Default::default()),
+            name: String::from("rust"),
+        };
+
+        let posts = Runtime::new()
+            .unwrap()
+            .block_on(subreddit.get_new(5))
+            .unwrap();
+
+        assert_eq!(posts.len(), 1);
+        _m1.assert();
+    }
+
+    #[test]
+    fn get_top() {
+        let body = format!(
+            "{{\"data\":{{\"kind\":\"Listing\",\"after\":\"t3_bbbbb\",\"children\":[{},{}]}}}}",
+            sample_item("aaaaa"),
+            sample_item("bbbbb")
+        );
+        let _m1 = mock("GET", "/r/rust/top?t=week&limit=2&show=all")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .create();
+        let api = Api::new(std::default::Default::default());
+        let subreddit = sample_subreddit(&api, "rust");
+
+        let posts = subreddit.get_top(2, Some(TimeFilter::Week)).unwrap();
+
+        assert_eq!(posts.len(), 2);
+        assert_eq!(posts[0].data.id, "aaaaa");
+        assert_eq!(posts[1].fullname(), "t3_bbbbb");
+        _m1.assert();
+    }
+
+    #[test]
+    fn listings() {
+        let body = format!(
+            "{{\"data\":{{\"kind\":\"Listing\",\"after\":\"t3_aaaaa\",\"children\":[{}]}}}}",
+            sample_item("aaaaa")
+        );
+        let api = Api::new(std::default::Default::default());
+        let subreddit = sample_subreddit(&api, "rust");
+        let mocks: Vec<_> = [
+            "hot?limit=1",
+            "new?limit=1",
+            "rising?limit=1",
+            "controversial?t=all&limit=1",
+            "gilded?limit=1",
+        ]
+        .iter()
+        .map(|path| {
+            mock("GET", format!("/r/rust/{}&show=all", path).as_str())
+                .with_status(200)
+                .with_body(&body)
+                .create()
+        })
+        .collect();
+
+        assert_eq!(subreddit.get_hot(1).unwrap().len(), 1);
+        assert_eq!(subreddit.get_new(1).unwrap().len(), 1);
+        assert_eq!(subreddit.get_rising(1).unwrap().len(), 1);
+        assert_eq!(
+            subreddit
+                .get_controversial(1, Some(TimeFilter::All))
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(subreddit.get_gilded(1).unwrap().len(), 1);
+        for m in mocks {
+            m.assert();
+        }
+    }
+
+    #[test]
+    fn mod_queues() {
+        let api = Api::new(std::default::Default::default());
+        let subreddit = sample_subreddit(&api, "modded");
+        let _m1 = mock("GET", "/r/modded/about/modqueue?limit=2&show=all")
+            .with_status(200)
+            .with_body(listing(&[
+                sample_item("qqq"),
+                sample_comment("ccc", "t3_qqq", 0, "\"\""),
+            ]))
+            .create();
+        let _m2 = mock("GET", "/r/modded/about/unmoderated?limit=1&show=all")
+            .with_status(200)
+            .with_body(listing(&[sample_item("uuu")]))
+            .create();
+
+        let items = subreddit.get_modqueue(2).unwrap();
+        let posts = subreddit.get_unmoderated(1).unwrap();
+
+        match (&items[0], &items[1]) {
+            (Item::Post(p), Item::Comment(c)) => {
+                assert_eq!(p.fullname(), "t3_qqq");
+                assert_eq!(c.fullname(), "t1_ccc");
+            }
+            _ => panic!("Expected a post and a comment"),
+        }
+        assert_eq!(posts[0].fullname(), "t3_uuu");
+        _m1.assert();
+        _m2.assert();
+    }
+
+    fn mod_action(id: &str, action: &str) -> String {
+        format!(
+            "{{\"kind\":\"modaction\",\"data\":{{\"id\":\"ModAction_{}\",\"action\":\"{}\", \
+             \"mod\":\"some-mod\",\"target_fullname\":\"t3_abc\",\"target_author\":\"someone\", \
+             \"details\":\"spam\",\"description\":null,\"created_utc\":1560000000.0}}}}",
+            id, action
+        )
+    }
+
+    #[test]
+    fn get_mod_log() {
+        let api = Api::new(std::default::Default::default());
+        let subreddit = sample_subreddit(&api, "modded");
+        let _m1 = mock(
+            "GET",
+            "/r/modded/about/log?mod=some-mod&type=removelink&limit=3&show=all",
+        )
+        .with_status(200)
+        .with_body(format!(
+            "{{\"data\":{{\"after\":\"ModAction_2\",\"children\":[{},{}]}}}}",
+            mod_action("1", "removelink"),
+            mod_action("2", "removelink")
+        ))
+        .create();
+        let _m2 = mock(
+            "GET",
+            "/r/modded/about/log?mod=some-mod&type=removelink&limit=3&after=ModAction_2&count=2&show=all",
+        )
+        .with_status(200)
+        .with_body(listing(&[mod_action("3", "removelink")]))
+        .create();
+
+        let entries = subreddit
+            .get_mod_log(3, Some("some-mod"), Some("removelink"))
+            .unwrap();
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].id, "ModAction_1");
+        assert_eq!(entries[0].moderator, "some-mod");
+        assert_eq!(entries[0].target_fullname, Some(String::from("t3_abc")));
+        assert_eq!(entries[2].id, "ModAction_3");
+        _m1.assert();
+        _m2.assert();
+    }
+
+    #[test]
+    fn get_banned() {
+        let api = Api::new(std::default::Default::default());
+        let subreddit = sample_subreddit(&api, "banning");
+        let _m1 = mock("GET", "/r/banning/about/banned?limit=2&show=all")
+            .with_status(200)
+            .with_body(
+                "{\"kind\":\"Listing\",\"data\":{\"after\":null,\"children\":[ \
+                 {\"name\":\"spammer\",\"id\":\"t2_aa\",\"rel_id\":\"rb_1\",\"date\":1560000000.0, \
+                 \"note\":\"Spam\",\"days_left\":3}, \
+                 {\"name\":\"troll\",\"id\":\"t2_bb\",\"rel_id\":\"rb_2\",\"date\":1560000000.0, \
+                 \"note\":\"\",\"days_left\":null}]}}",
+            )
+            .create();
+
+        let banned = subreddit.get_banned(2).unwrap();
+
+        assert_eq!(banned.len(), 2);
+        assert_eq!(banned[0].name, "spammer");
+        assert_eq!(banned[0].days_left, Some(3));
+        assert_eq!(banned[1].fullname, "t2_bb");
+        assert_eq!(banned[1].days_left, None);
+        _m1.assert();
+    }
+
+    #[test]
+    fn get_moderators() {
+        let api = Api::new(std::default::Default::default());
+        let subreddit = sample_subreddit(&api, "modding");
+        let children: Vec<String> = (0..150)
+            .map(|i| {
+                format!(
+                    "{{\"name\":\"mod{0}\",\"id\":\"t2_{0}\",\"date\":1560000000.0, \
+                     \"mod_permissions\":[\"posts\",\"wiki\"]}}",
+                    i
+                )
+            })
+            .collect();
+        let _m1 = mock("GET", "/r/modding/about/moderators")
+            .with_status(200)
+            .with_body(format!(
+                "{{\"kind\":\"UserList\",\"data\":{{\"children\":[{}]}}}}",
+                children.join(",")
+            ))
+            .create();
+
+        let moderators = subreddit.get_moderators().unwrap();
+
+        assert_eq!(moderators.len(), 150);
+        assert_eq!(moderators[0].name, "mod0");
+        assert_eq!(moderators[0].permissions, vec!["posts", "wiki"]);
+        _m1.assert();
+    }
+
+    fn check_ban(name: &str, options: BanOptions, field: &str) {
+        let api = Api::new(std::default::Default::default());
+        let subreddit = sample_subreddit(&api, name);
+        let _m1 = mock("POST", format!("/r/{}/api/friend", name).as_str())
+            .match_body(form_field(field))
+            .with_status(200)
+            .with_body("{\"json\":{\"errors\":[]}}")
+            .create();
+
+        subreddit.ban("spammer", options).unwrap();
+
+        _m1.assert();
+    }
+
+    #[test]
+    fn ban() {
+        check_ban("banning", BanOptions::new(), "type=banned");
+    }
+
+    #[test]
+    fn ban_duration() {
+        check_ban(
+            "banning_duration",
+            BanOptions::new().duration(Some(7)),
+            "duration=7",
+        );
+    }
+
+    #[test]
+    fn ban_reason() {
+        check_ban(
+            "banning_reason",
+            BanOptions::new().reason(Some("Spam")),
+            "ban_reason=Spam",
+        );
+    }
+
+    #[test]
+    fn ban_note() {
+        check_ban(
+            "banning_note",
+            BanOptions::new().note(Some("Repeat")),
+            "note=Repeat",
+        );
+    }
+
+    #[test]
+    fn ban_message() {
+        check_ban(
+            "banning_message",
+            BanOptions::new().message(Some("Bye!")),
+            "ban_message=Bye%21",
+        );
+    }
+
+    #[test]
+    fn invite_moderator() {
+        let api = Api::new(std::default::Default::default());
+        let subreddit = sample_subreddit(&api, "inviting");
+        let _m1 = mock("POST", "/r/inviting/api/friend")
+            .match_body(form_field("permissions=%2Bposts%2C%2Bmail"))
+            .with_status(200)
+            .with_body("{\"json\":{\"errors\":[]}}")
+            .create();
+        let _m2 = mock("POST", "/r/inviting/api/unfriend")
+            .match_body(form_field("type=moderator_invite"))
+            .with_status(200)
+            .with_body("{\"json\":{\"errors\":[]}}")
+            .create();
+
+        subreddit
+            .invite_moderator("some-user", &["posts", "mail"])
+            .unwrap();
+        subreddit.revoke_moderator_invite("some-user").unwrap();
+
+        _m1.assert();
+        _m2.assert();
+    }
+
+    fn stream_options() -> StreamOptions {
+        StreamOptions::new()
+            .limit(3)
+            .min_delay(Duration::from_millis(0))
+            .max_delay(Duration::from_millis(0))
+    }
+
+    #[test]
+    fn stream_submissions() {
+        let api = Api::new(std::default::Default::default());
+        let subreddit = sample_subreddit(&api, "stream1");
+        let mut stream = subreddit.stream_submissions(stream_options());
+        let page = |ids: &[&str]| {
+            let items: Vec<String> = ids.iter().map(|id| sample_item(id)).collect();
+            mock("GET", "/r/stream1/new?limit=3&show=all")
+                .with_status(200)
+                .with_body(listing(&items))
+                .create()
+        };
+
+        let m1 = page(&["bbb", "aaa"]);
+        assert_eq!(stream.next().unwrap().unwrap().data.id, "aaa");
+        assert_eq!(stream.next().unwrap().unwrap().data.id, "bbb");
+        drop(m1);
+
+        let _m2 = page(&["ccc", "bbb", "aaa"]);
+        assert_eq!(stream.next().unwrap().unwrap().data.id, "ccc");
+    }
+
+    #[test]
+    fn stream_comments_skip_existing() {
+        let api = Api::new(std::default::Default::default());
+        let subreddit = sample_subreddit(&api, "stream2");
+        let mut stream = subreddit.stream_comments(stream_options().skip_existing(true));
+        let page = |ids: &[&str]| {
+            let items: Vec<String> = ids
+                .iter()
+                .map(|id| sample_comment(id, "t3_abc", 0, "\"\""))
+                .collect();
+            mock("GET", "/r/stream2/comments?limit=3&show=all")
+                .with_status(200)
+                .with_body(listing(&items))
+                .create()
+        };
+
+        let m1 = page(&["bbb", "aaa"]);
+        stream.poll().unwrap();
+        drop(m1);
+
+        let _m2 = page(&["ddd", "ccc", "bbb"]);
+        assert_eq!(stream.next().unwrap().unwrap().fullname(), "t1_ccc");
+        assert_eq!(stream.next().unwrap().unwrap().fullname(), "t1_ddd");
+    }
+
+    fn by_id_response(id: &str) -> String {
+        format!(
+            "{{\"kind\":\"Listing\",\"data\":{{\"children\":[{}]}}}}",
+            sample_item(id)
+        )
+    }
+
+    fn submitted_response(id: &str) -> String {
+        format!(
+            "{{\"json\":{{\"errors\":[],\"data\":{{\"id\":\"{0}\",\"name\":\"t3_{0}\"}}}}}}",
+            id
+        )
+    }
+
+    #[test]
+    fn submit_text() {
+        let _m1 = mock("POST", "/api/submit")
+            .match_body(form_field("flair_text=Announcement"))
+            .with_status(200)
+            .with_body(submitted_response("txt"))
+            .create();
+        let _m2 = mock("GET", "/by_id/t3_txt")
+            .with_status(200)
+            .with_body(by_id_response("txt"))
+            .create();
+        let api = Api::new(std::default::Default::default());
+        let subreddit = sample_subreddit(&api, "rust");
+
+        let options = SubmitOptions::new()
+            .flair_text(Some("Announcement"))
+            .spoiler(true);
+        let post = subreddit.submit_text("Title", "Text", options).unwrap();
+
+        assert_eq!(post.data.id, "txt");
+        _m1.assert();
+        _m2.assert();
+    }
+
+    #[test]
+    fn submit_link() {
+        let _m1 = mock("POST", "/api/submit")
+            .match_body(form_field("url=https%3A%2F%2Fwww.rust-lang.org"))
+            .with_status(200)
+            .with_body(
+                "{\"json\":{\"errors\":[[\"ALREADY_SUB\",\"that link has already been submitted\",\"url\"]]}}",
+            )
+            .create();
+        let api = Api::new(std::default::Default::default());
+        let subreddit = sample_subreddit(&api, "rust");
+
+        match subreddit.submit_link("Title", "https://www.rust-lang.org", SubmitOptions::new()) {
+            Err(ApiError::Reddit(errors)) => {
+                assert_eq!(errors[0].code, "ALREADY_SUB");
+                assert_eq!(errors[0].field, Some(String::from("url")));
+            }
+            _ => panic!("Expected Reddit error"),
+        }
+        _m1.assert();
+    }
+
+    #[test]
+    fn submit_crosspost() {
+        let _m1 = mock("POST", "/api/submit")
+            .match_body(form_field("crosspost_fullname=t3_orig"))
+            .with_status(200)
+            .with_body(submitted_response("xps"))
+            .create();
+        let _m2 = mock("GET", "/by_id/t3_xps")
+            .with_status(200)
+            .with_body(by_id_response("xps"))
+            .create();
+        let api = Api::new(std::default::Default::default());
+        let subreddit = sample_subreddit(&api, "rust");
+        let item: Value = serde_json::from_str(&sample_item("orig")).unwrap();
+        let original = Post::from_listing_item(&api, &item).unwrap();
+
+        let post = subreddit
+            .submit_crosspost(&original, "Title", SubmitOptions::new().nsfw(true))
+            .unwrap();
+
+        assert_eq!(post.data.id, "xps");
+        _m1.assert();
+        _m2.assert();
+    }
+
+    #[test]
+    fn submit_options() {
+        let form_data = SubmitOptions::new().flair_id(Some("abc")).form_data();
+
+        assert_eq!(form_data.get("flair_id"), Some(&"abc"));
+        assert_eq!(form_data.get("flair_text"), None);
+        assert_eq!(form_data.get("sendreplies"), Some(&"true"));
+        assert_eq!(form_data.get("resubmit"), Some(&"false"));
+    }
+}
@@ -6,19 +6,130 @@
 //! let user = api.get_user("some-username")?;
 //! ```
 
-use crate::Api;
+use super::comment::Comment;
+use super::post::Post;
+use super::{from_thing, ApiClient, ApiHandle, Item};
+use crate::query_listing::{QueryListingRequest, TimeFilter};
+use crate::{Api, ApiError};
+use serde::Deserialize;
 use serde_json::Value;
 
+/// The kind of user items.
+pub const KIND: &str = "t2";
+
+/// A user's profile, as returned by the API for `t2` items.
+///
+/// Suspended accounts only have their name and the
+/// `suspended` flag, with every other field left empty.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct UserData {
+    /// The user's id, i.e. "1w72"
+    #[serde(default)]
+    pub id: String,
+    /// The user's name
+    pub name: String,
+    /// Karma from posts
+    #[serde(default)]
+    pub link_karma: i64,
+    /// Karma from comments
+    #[serde(default)]
+    pub comment_karma: i64,
+    /// Karma from receiving awards
+    #[serde(default)]
+    pub awardee_karma: i64,
+    /// Karma from giving awards
+    #[serde(default)]
+    pub awarder_karma: i64,
+    /// The sum of all karma
+    #[serde(default)]
+    pub total_karma: i64,
+    /// When the account was created, in seconds since the epoch
+    #[serde(default)]
+    pub created_utc: f64,
+    /// Whether the user moderates any subreddits
+    #[serde(default)]
+    pub is_mod: bool,
+    /// Whether the user has Reddit Premium
+    #[serde(default)]
+    pub is_gold: bool,
+    /// Whether the user is a Reddit employee
+    #[serde(default)]
+    pub is_employee: bool,
+    /// Whether the user has a verified email address
+    #[serde(default)]
+    pub verified: bool,
+    /// Whether the account is suspended
+    #[serde(default, rename = "is_suspended")]
+    pub suspended: bool,
+    /// The URL of the user's avatar
+    #[serde(default)]
+    pub icon_img: String,
+}
+
+/// The sort order of a user's history listings.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UserSort {
+    /// Newest first.
+    New,
+    /// Currently popular first.
+    Hot,
+    /// Highest score first, within the time filter.
+    Top,
+    /// Most controversial first, within the time filter.
+    Controversial,
+}
+
+impl UserSort {
+    /// The value to pass as the `sort` parameter.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UserSort::New => "new",
+            UserSort::Hot => "hot",
+            UserSort::Top => "top",
+            UserSort::Controversial => "controversial",
+        }
+    }
+}
+
 /// Maps to a single user.
-#[derive(Clone)]
-pub struct User<'a> {
+///
+/// The `A` type parameter is the API client that the struct
+/// was retrieved from, either `Api` or `AsyncApi`.
+pub struct User<'a, A: ApiClient<'a> = Api> {
     /// Rerefence to the source `Api` struct. Used for calling API endpoints.
-    pub api: &'a Api,
-    /// User's "about" information.
-    pub about: Value,
+    pub api: A::Handle,
+    /// User's profile.
+    pub data: UserData,
 }
 
-impl<'a> User<'a> {
+impl<'a, A: ApiClient<'a>> Clone for User<'a, A> {
+    fn clone(&self) -> Self {
+        User {
+            api: self.api.clone(),
+            data: self.data.clone(),
+        }
+    }
+}
+
+impl<'a, A: ApiClient<'a>> User<'a, A> {
+    /// Create a user struct from the response of the
+    /// `user/{name}/about` endpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `api` - the API client
+    /// * `item` - the response body
+    pub fn from_about<H>(api: H, item: &Value) -> Result<Self, ApiError>
+    where
+        H: ApiHandle<'a, Client = A>,
+        A: ApiClient<'a, Handle = H>,
+    {
+        Ok(User {
+            api,
+            data: from_thing(item, KIND)?,
+        })
+    }
+
     /// Get the user's name.
     ///
     /// # Examples
@@ -26,28 +137,317 @@ impl<'a> User<'a> {
     /// ```rust,no_run,ignore
     /// let name = user.name();
     /// ```
-    pub fn name(&self) -> String {
-        self.about["data"]["name"].as_str().unwrap().to_owned()
+    pub fn name(&self) -> &str {
+        &self.data.name
+    }
+}
+
+impl<'a> User<'a> {
+    /// Get `count` items from one of the user's history listings.
+    fn get_listing<T>(
+        &self,
+        which: &str,
+        count: u64,
+        sort: Option<UserSort>,
+        time: Option<TimeFilter>,
+        parse: fn(&'a Api, &Value) -> Result<T, ApiError>,
+    ) -> Result<Vec<T>, ApiError> {
+        let path = format!("user/{}/{}", self.data.name, which);
+        let mut params = Vec::new();
+        if let Some(sort) = sort {
+            params.push(("sort", sort.as_str()));
+        }
+        if let Some(time) = time {
+            params.push(("t", time.as_str()));
+        }
+        let ql = QueryListingRequest::new(&path, count.min(100), 0).params(&params);
+        self.api
+            .listing_iter(ql)
+            .take(count as usize)
+            .map(|item| parse(self.api, &item?))
+            .collect()
+    }
+
+    /// Get `count` items from one of the listings that only
+    /// the logged-in user can see for their own account.
+    fn get_private_listing<T>(
+        &self,
+        which: &str,
+        count: u64,
+        parse: fn(&'a Api, &Value) -> Result<T, ApiError>,
+    ) -> Result<Vec<T>, ApiError> {
+        // usernames are case-insensitive
+        let is_own = matches!(
+            self.api.get_username(),
+            Some(username) if username.eq_ignore_ascii_case(&self.data.name)
+        );
+        if !is_own {
+            return Err(ApiError::Other(format!(
+                "The '{}' listing is only available for the logged-in user",
+                which
+            )));
+        }
+        self.get_listing(which, count, None, None, parse)
+    }
+
+    /// Get `count` of the user's posts.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of posts to retrieve
+    /// * `sort` - the order of the posts, or `None` for Reddit's default
+    /// * `time` - the time window for the `Top` and `Controversial` sorts
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let posts = user.get_submitted(25, Some(UserSort::Top), Some(TimeFilter::Year))?;
+    /// ```
+    pub fn get_submitted(
+        &self,
+        count: u64,
+        sort: Option<UserSort>,
+        time: Option<TimeFilter>,
+    ) -> Result<Vec<Post<'a>>, ApiError> {
+        self.get_listing("submitted", count, sort, time, Post::from_listing_item)
+    }
+
+    /// Get `count` of the user's comments.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of comments to retrieve
+    /// * `sort` - the order of the comments, or `None` for Reddit's default
+    /// * `time` - the time window for the `Top` and `Controversial` sorts
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let comments = user.get_comments(25, Some(UserSort::New), None)?;
+    /// ```
+    pub fn get_comments(
+        &self,
+        count: u64,
+        sort: Option<UserSort>,
+        time: Option<TimeFilter>,
+    ) -> Result<Vec<Comment<'a>>, ApiError> {
+        self.get_listing("comments", count, sort, time, Comment::from_listing_item)
+    }
+
+    /// Get `count` of the user's posts and comments.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of items to retrieve
+    /// * `sort` - the order of the items, or `None` for Reddit's default
+    /// * `time` - the time window for the `Top` and `Controversial` sorts
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// for item in user.get_overview(25, None, None)? {
+    ///     println!("{}", item.fullname());
+    /// }
+    /// ```
+    pub fn get_overview(
+        &self,
+        count: u64,
+        sort: Option<UserSort>,
+        time: Option<TimeFilter>,
+    ) -> Result<Vec<Item<'a>>, ApiError> {
+        self.get_listing("overview", count, sort, time, Item::from_listing_item)
+    }
+
+    /// Get `count` of the user's posts and comments that were gilded.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of items to retrieve
+    pub fn get_gilded(&self, count: u64) -> Result<Vec<Item<'a>>, ApiError> {
+        self.get_listing("gilded", count, None, None, Item::from_listing_item)
+    }
+
+    /// Get `count` of the posts the user upvoted.
+    ///
+    /// Only available for the logged-in user.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of posts to retrieve
+    pub fn get_upvoted(&self, count: u64) -> Result<Vec<Post<'a>>, ApiError> {
+        self.get_private_listing("upvoted", count, Post::from_listing_item)
+    }
+
+    /// Get `count` of the posts the user downvoted.
+    ///
+    /// Only available for the logged-in user.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of posts to retrieve
+    pub fn get_downvoted(&self, count: u64) -> Result<Vec<Post<'a>>, ApiError> {
+        self.get_private_listing("downvoted", count, Post::from_listing_item)
+    }
+
+    /// Get `count` of the posts and comments the user saved.
+    ///
+    /// Only available for the logged-in user.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of items to retrieve
+    pub fn get_saved(&self, count: u64) -> Result<Vec<Item<'a>>, ApiError> {
+        self.get_private_listing("saved", count, Item::from_listing_item)
+    }
+
+    /// Get `count` of the posts the user hid.
+    ///
+    /// Only available for the logged-in user.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - number of posts to retrieve
+    pub fn get_hidden(&self, count: u64) -> Result<Vec<Post<'a>>, ApiError> {
+        self.get_private_listing("hidden", count, Post::from_listing_item)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::User;
+    use super::{User, UserSort};
+    use crate::errors::ApiError;
+    use crate::models::comment::tests::{listing, sample_comment};
+    use crate::models::post::tests::sample_item;
+    use crate::models::Item;
+    use crate::query_listing::TimeFilter;
     use crate::Api;
     use lazy_static::lazy_static;
+    use mockito::mock;
+    use serde_json::Value;
 
     lazy_static! {
         static ref API: Api = Api::new(std::default::Default::default());
     }
 
+    fn sample_about(name: &str) -> String {
+        format!(
+            "{{\"kind\":\"t2\",\"data\":{{\"id\":\"1w72\",\"name\":\"{}\",\"link_karma\":10, \
+             \"comment_karma\":20,\"awardee_karma\":0,\"awarder_karma\":0,\"total_karma\":30, \
+             \"created_utc\":1500000000.0,\"is_mod\":true,\"is_gold\":false, \
+             \"is_employee\":false,\"verified\":true,\"icon_img\":\"\"}}}}",
+            name
+        )
+    }
+
+    fn user(name: &str) -> User<'static> {
+        let about: Value = serde_json::from_str(&sample_about(name)).unwrap();
+        User::from_about(&*API, &about).unwrap()
+    }
+
     #[test]
     fn name() {
-        let u = User {
-            api: &API,
-            about: serde_json::from_str("{\"data\":{\"name\":\"test\"}}").unwrap(),
-        };
+        let u = user("test");
+
+        assert_eq!(u.name(), "test");
+        assert_eq!(u.data.total_karma, 30);
+        assert!(u.data.is_mod);
+        assert!(u.data.verified);
+        assert!(!u.data.suspended);
+    }
+
+    #[test]
+    fn suspended() {
+        let about: Value = serde_json::from_str(
+            "{\"kind\":\"t2\",\"data\":{\"name\":\"gone\",\"is_suspended\":true}}",
+        )
+        .unwrap();
+        let u = User::from_about(&*API, &about).unwrap();
+
+        assert_eq!(u.name(), "gone");
+        assert!(u.data.suspended);
+        assert_eq!(u.data.link_karma, 0);
+    }
+
+    #[test]
+    fn get_user() {
+        let _m1 = mock("GET", "/user/someone/about")
+            .with_status(200)
+            .with_body(sample_about("someone"))
+            .create();
+
+        let u = API.get_user("someone").unwrap();
+
+        assert_eq!(u.name(), "someone");
+        assert_eq!(u.data.comment_karma, 20);
+        _m1.assert();
+    }
+
+    #[test]
+    fn get_submitted() {
+        let _m1 = mock(
+            "GET",
+            "/user/poster/submitted?sort=top&t=year&limit=1&show=all",
+        )
+        .with_status(200)
+        .with_body(format!(
+            "{{\"data\":{{\"after\":null,\"children\":[{}]}}}}",
+            sample_item("aaa")
+        ))
+        .create();
+
+        let posts = user("poster")
+            .get_submitted(1, Some(UserSort::Top), Some(TimeFilter::Year))
+            .unwrap();
 
-        assert_eq!(u.name(), "test")
+        assert_eq!(posts[0].data.id, "aaa");
+        _m1.assert();
+    }
+
+    #[test]
+    fn get_overview() {
+        let _m1 = mock("GET", "/user/mixed/overview?limit=2&show=all")
+            .with_status(200)
+            .with_body(listing(&[
+                sample_item("aaa"),
+                sample_comment("bbb", "t3_aaa", 0, "\"\""),
+            ]))
+            .create();
+
+        let items = user("mixed").get_overview(2, None, None).unwrap();
+
+        match (&items[0], &items[1]) {
+            (Item::Post(p), Item::Comment(c)) => {
+                assert_eq!(p.data.id, "aaa");
+                assert_eq!(c.data.id, "bbb");
+            }
+            _ => panic!("Expected a post and a comment"),
+        }
+        assert_eq!(items[1].fullname(), "t1_bbb");
+        _m1.assert();
+    }
+
+    #[test]
+    fn private_listing_ignores_case() {
+        let mut api = Api::new(std::default::Default::default());
+        api.whoami = Some(serde_json::json!({"name": "someuser"}));
+        let _m1 = mock("GET", "/user/SomeUser/hidden?limit=1&show=all")
+            .with_status(200)
+            .with_body(listing(&[sample_item("hhh")]))
+            .create();
+        let about: Value = serde_json::from_str(&sample_about("SomeUser")).unwrap();
+        let user = User::from_about(&api, &about).unwrap();
+
+        let posts = user.get_hidden(1).unwrap();
+
+        assert_eq!(posts[0].data.id, "hhh");
+        _m1.assert();
+    }
+
+    #[test]
+    fn private_listing_for_other_user() {
+        match user("someone-else").get_saved(10) {
+            Err(ApiError::Other(_)) => (),
+            _ => panic!("Expected error"),
+        }
     }
 }
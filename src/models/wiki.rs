@@ -0,0 +1,551 @@
+//! Struct-based access to subreddit wikis.
+//!
+//! Read a wiki page with:
+//!
+//! ```rust,no_run,ignore
+//! let page = api.get_subreddit("rust")?.get_wiki_page("config/bot")?;
+//! println!("{}", page.content_md);
+//! ```
+
+use super::subreddit::Subreddit;
+use crate::query_listing::QueryListingRequest;
+use crate::{parse_json, ApiError};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Who may edit a wiki page.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WikiPermission {
+    /// Anyone allowed by the subreddit's wiki settings.
+    Subreddit,
+    /// Only approved wiki contributors.
+    ApprovedEditors,
+    /// Only moderators.
+    Moderators,
+}
+
+impl WikiPermission {
+    /// The value to pass as the `permlevel` parameter.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WikiPermission::Subreddit => "0",
+            WikiPermission::ApprovedEditors => "1",
+            WikiPermission::Moderators => "2",
+        }
+    }
+}
+
+/// Parse the `permlevel` number of a page's settings.
+fn permission<'de, D>(deserializer: D) -> Result<WikiPermission, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match u64::deserialize(deserializer)? {
+        0 => Ok(WikiPermission::Subreddit),
+        1 => Ok(WikiPermission::ApprovedEditors),
+        2 => Ok(WikiPermission::Moderators),
+        level => Err(D::Error::custom(format!(
+            "unknown wiki permission level {}",
+            level
+        ))),
+    }
+}
+
+/// Parse the name out of a `t2` user item.
+fn user_name<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: Value = Deserialize::deserialize(deserializer)?;
+    Ok(value["data"]["name"].as_str().map(str::to_owned))
+}
+
+/// Parse the names out of a list of `t2` user items.
+fn user_names<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let values: Vec<Value> = Deserialize::deserialize(deserializer)?;
+    Ok(values
+        .iter()
+        .filter_map(|value| value["data"]["name"].as_str().map(str::to_owned))
+        .collect())
+}
+
+/// A wiki page's content and latest revision, as returned
+/// by the API for `wikipage` items.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct WikiPage {
+    /// The page's markdown text
+    pub content_md: String,
+    /// The page's text rendered as HTML
+    #[serde(default)]
+    pub content_html: String,
+    /// Whether the account may edit the page
+    #[serde(default)]
+    pub may_revise: bool,
+    /// The id of the page's latest revision
+    #[serde(default)]
+    pub revision_id: Option<String>,
+    /// When the latest revision was made, in seconds since the epoch
+    #[serde(default)]
+    pub revision_date: Option<f64>,
+    /// The username of the latest revision's author
+    #[serde(default, deserialize_with = "user_name")]
+    pub revision_by: Option<String>,
+    /// The reason given for the latest revision
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// A single revision of a wiki page.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct WikiRevision {
+    /// The revision's id
+    pub id: String,
+    /// The name of the revised page
+    pub page: String,
+    /// The username of the revision's author
+    #[serde(default, deserialize_with = "user_name")]
+    pub author: Option<String>,
+    /// The reason given for the revision
+    #[serde(default)]
+    pub reason: Option<String>,
+    /// When the revision was made, in seconds since the epoch
+    pub timestamp: f64,
+    /// Whether the revision is hidden from the page's history
+    #[serde(default, rename = "revision_hidden")]
+    pub hidden: bool,
+}
+
+/// A wiki page's permissions, as returned by the
+/// API for `wikipagesettings` items.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct WikiPageSettings {
+    /// Who may edit the page
+    #[serde(rename = "permlevel", deserialize_with = "permission")]
+    pub permission: WikiPermission,
+    /// Whether the page is shown in the list of the wiki's pages
+    #[serde(default)]
+    pub listed: bool,
+    /// The usernames of the users allowed to edit the page
+    /// regardless of its permission
+    #[serde(default, deserialize_with = "user_names")]
+    pub editors: Vec<String>,
+}
+
+/// Turn the errors for wiki endpoints into the matching wiki error.
+///
+/// The API responds with a 404 for missing pages, and a 403 with the
+/// reason in the body when the account may not view or edit the page.
+fn wiki_error(page: &str, error: ApiError) -> ApiError {
+    match error {
+        ApiError::Http { status: 404, .. } => ApiError::WikiPageNotFound {
+            page: page.to_owned(),
+        },
+        ApiError::Http { status: 403, body } => {
+            let data: Value = serde_json::from_str(&body).unwrap_or_default();
+            ApiError::WikiPermissionDenied {
+                page: page.to_owned(),
+                reason: data["reason"].as_str().unwrap_or_default().to_owned(),
+            }
+        }
+        error => error,
+    }
+}
+
+impl<'a> Subreddit<'a> {
+    /// Send a request to one of the subreddit's wiki endpoints, returning
+    /// its `data`. Errors for requests about a `page` are turned into the
+    /// matching wiki error.
+    fn wiki_query(
+        &self,
+        method: &str,
+        path: &str,
+        page: Option<&str>,
+        form_data: Option<HashMap<&str, &str>>,
+    ) -> Result<Value, ApiError> {
+        let path = format!("r/{}/{}", self.name, path);
+        let mut resp = self
+            .api
+            .query(method, &path, None, form_data)
+            .map_err(|e| match page {
+                Some(page) => wiki_error(page, e),
+                None => e,
+            })?;
+        let data: Value = parse_json(&mut resp)?;
+        Ok(data["data"].clone())
+    }
+
+    /// Get the names of the subreddit's wiki pages.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// let pages = subreddit.get_wiki_pages()?;
+    /// ```
+    pub fn get_wiki_pages(&self) -> Result<Vec<String>, ApiError> {
+        let data = self.wiki_query("GET", "wiki/pages", None, None)?;
+        Ok(serde_json::from_value(data)?)
+    }
+
+    /// Get a wiki page's content and latest revision.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - the page's name, i.e. "index" or "config/bot"
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// match subreddit.get_wiki_page("config/bot") {
+    ///     Ok(page) => println!("{}", page.content_md),
+    ///     Err(ApiError::WikiPageNotFound { .. }) => println!("No config yet"),
+    ///     Err(err) => panic!(err),
+    /// }
+    /// ```
+    pub fn get_wiki_page(&self, page: &str) -> Result<WikiPage, ApiError> {
+        let data = self.wiki_query("GET", &format!("wiki/{}", page), Some(page), None)?;
+        Ok(serde_json::from_value(data)?)
+    }
+
+    /// Replace a wiki page's content, creating the page if it doesn't exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - the page's name
+    /// * `content` - the page's new markdown text
+    /// * `reason` - the reason for the revision, shown in the page's history
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// subreddit.edit_wiki_page("config/bot", "interval: 60", Some("Slow down"))?;
+    /// ```
+    pub fn edit_wiki_page(
+        &self,
+        page: &str,
+        content: &str,
+        reason: Option<&str>,
+    ) -> Result<(), ApiError> {
+        let mut form_data = HashMap::new();
+        form_data.insert("page", page);
+        form_data.insert("content", content);
+        if let Some(reason) = reason {
+            form_data.insert("reason", reason);
+        }
+        self.wiki_query("POST", "api/wiki/edit", Some(page), Some(form_data))?;
+        Ok(())
+    }
+
+    /// Get the `count` latest revisions of a wiki page, newest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - the page's name
+    /// * `count` - number of revisions to retrieve
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// for revision in subreddit.get_wiki_revisions("config/bot", 10)? {
+    ///     println!("{:?}: {:?}", revision.author, revision.reason);
+    /// }
+    /// ```
+    pub fn get_wiki_revisions(
+        &self,
+        page: &str,
+        count: u64,
+    ) -> Result<Vec<WikiRevision>, ApiError> {
+        let path = format!("r/{}/wiki/revisions/{}", self.name, page);
+        let ql = QueryListingRequest::new(&path, count.min(100), 0);
+        self.api
+            .listing_iter(ql)
+            .take(count as usize)
+            .map(|item| {
+                Ok(serde_json::from_value(
+                    item.map_err(|e| wiki_error(page, e))?,
+                )?)
+            })
+            .collect()
+    }
+
+    /// Revert a wiki page to one of its revisions.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - the page's name
+    /// * `revision` - the id of the revision to revert to
+    pub fn revert_wiki_page(&self, page: &str, revision: &str) -> Result<(), ApiError> {
+        let mut form_data = HashMap::new();
+        form_data.insert("page", page);
+        form_data.insert("revision", revision);
+        self.wiki_query("POST", "api/wiki/revert", Some(page), Some(form_data))?;
+        Ok(())
+    }
+
+    /// Get a wiki page's permissions.
+    ///
+    /// Requires the account to moderate the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - the page's name
+    pub fn get_wiki_settings(&self, page: &str) -> Result<WikiPageSettings, ApiError> {
+        let data = self.wiki_query("GET", &format!("wiki/settings/{}", page), Some(page), None)?;
+        Ok(serde_json::from_value(data)?)
+    }
+
+    /// Change a wiki page's permissions, returning the updated permissions.
+    ///
+    /// Requires the account to moderate the subreddit.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - the page's name
+    /// * `permission` - who may edit the page
+    /// * `listed` - whether to show the page in the list of the wiki's pages
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run,ignore
+    /// subreddit.update_wiki_settings("config/bot", WikiPermission::Moderators, false)?;
+    /// ```
+    pub fn update_wiki_settings(
+        &self,
+        page: &str,
+        permission: WikiPermission,
+        listed: bool,
+    ) -> Result<WikiPageSettings, ApiError> {
+        let mut form_data = HashMap::new();
+        form_data.insert("page", page);
+        form_data.insert("permlevel", permission.as_str());
+        form_data.insert("listed", if listed { "true" } else { "false" });
+        let path = format!("wiki/settings/{}", page);
+        let data = self.wiki_query("POST", &path, Some(page), Some(form_data))?;
+        Ok(serde_json::from_value(data)?)
+    }
+
+    /// Allow a user to edit a wiki page regardless of its permission.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - the page's name
+    /// * `username` - the user's name
+    pub fn add_wiki_editor(&self, page: &str, username: &str) -> Result<(), ApiError> {
+        let mut form_data = HashMap::new();
+        form_data.insert("page", page);
+        form_data.insert("username", username);
+        self.wiki_query(
+            "POST",
+            "api/wiki/alloweditor/add",
+            Some(page),
+            Some(form_data),
+        )?;
+        Ok(())
+    }
+
+    /// Stop allowing a user to edit a wiki page regardless of its permission.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - the page's name
+    /// * `username` - the user's name
+    pub fn remove_wiki_editor(&self, page: &str, username: &str) -> Result<(), ApiError> {
+        let mut form_data = HashMap::new();
+        form_data.insert("page", page);
+        form_data.insert("username", username);
+        self.wiki_query(
+            "POST",
+            "api/wiki/alloweditor/del",
+            Some(page),
+            Some(form_data),
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{WikiPageSettings, WikiPermission};
+    use crate::errors::ApiError;
+    use crate::models::comment::tests::listing;
+    use crate::models::post::tests::form_field;
+    use crate::models::subreddit::tests::sample_subreddit;
+    use crate::Api;
+    use mockito::mock;
+
+    #[test]
+    fn get_wiki_pages() {
+        let api = Api::new(std::default::Default::default());
+        let subreddit = sample_subreddit(&api, "wiki1");
+        let _m1 = mock("GET", "/r/wiki1/wiki/pages")
+            .with_status(200)
+            .with_body("{\"kind\":\"wikipagelisting\",\"data\":[\"index\",\"config/bot\"]}")
+            .create();
+
+        let pages = subreddit.get_wiki_pages().unwrap();
+
+        assert_eq!(pages, vec!["index", "config/bot"]);
+        _m1.assert();
+    }
+
+    #[test]
+    fn get_wiki_pages_disabled() {
+        let api = Api::new(std::default::Default::default());
+        let subreddit = sample_subreddit(&api, "wiki7");
+        let _m1 = mock("GET", "/r/wiki7/wiki/pages")
+            .with_status(403)
+            .with_body("{\"reason\":\"WIKI_DISABLED\",\"message\":\"Forbidden\"}")
+            .create();
+
+        match subreddit.get_wiki_pages() {
+            Err(ApiError::Http { status: 403, .. }) => (),
+            _ => panic!("Expected HTTP error"),
+        }
+    }
+
+    #[test]
+    fn get_wiki_page() {
+        let api = Api::new(std::default::Default::default());
+        let subreddit = sample_subreddit(&api, "wiki2");
+        let _m1 = mock("GET", "/r/wiki2/wiki/config/bot")
+            .with_status(200)
+            .with_body(
+                "{\"kind\":\"wikipage\",\"data\":{\"content_md\":\"interval: 60\", \
+                 \"content_html\":\"<p>interval: 60</p>\",\"may_revise\":true, \
+                 \"revision_id\":\"abc-123\",\"revision_date\":1560000000, \
+                 \"revision_by\":{\"kind\":\"t2\",\"data\":{\"name\":\"some-mod\"}}, \
+                 \"reason\":null}}",
+            )
+            .create();
+
+        let page = subreddit.get_wiki_page("config/bot").unwrap();
+
+        assert_eq!(page.content_md, "interval: 60");
+        assert!(page.may_revise);
+        assert_eq!(page.revision_id, Some(String::from("abc-123")));
+        assert_eq!(page.revision_by, Some(String::from("some-mod")));
+        assert_eq!(page.reason, None);
+        _m1.assert();
+    }
+
+    #[test]
+    fn get_wiki_page_errors() {
+        let api = Api::new(std::default::Default::default());
+        let subreddit = sample_subreddit(&api, "wiki3");
+        let _m1 = mock("GET", "/r/wiki3/wiki/missing")
+            .with_status(404)
+            .with_body("{\"reason\":\"PAGE_NOT_CREATED\",\"message\":\"Not Found\"}")
+            .create();
+        let _m2 = mock("GET", "/r/wiki3/wiki/secret")
+            .with_status(403)
+            .with_body("{\"reason\":\"MAY_NOT_VIEW\",\"message\":\"Forbidden\"}")
+            .create();
+
+        match subreddit.get_wiki_page("missing") {
+            Err(ApiError::WikiPageNotFound { page }) => assert_eq!(page, "missing"),
+            _ => panic!("Expected not found error"),
+        }
+        match subreddit.get_wiki_page("secret") {
+            Err(ApiError::WikiPermissionDenied { page, reason }) => {
+                assert_eq!(page, "secret");
+                assert_eq!(reason, "MAY_NOT_VIEW");
+            }
+            _ => panic!("Expected permission denied error"),
+        }
+    }
+
+    #[test]
+    fn edit_wiki_page() {
+        let api = Api::new(std::default::Default::default());
+        let subreddit = sample_subreddit(&api, "wiki4");
+        let _m1 = mock("POST", "/r/wiki4/api/wiki/edit")
+            .match_body(form_field("reason=Slow\\+down"))
+            .with_status(200)
+            .with_body("{}")
+            .create();
+
+        subreddit
+            .edit_wiki_page("config/bot", "interval: 120", Some("Slow down"))
+            .unwrap();
+
+        _m1.assert();
+    }
+
+    #[test]
+    fn get_wiki_revisions() {
+        let api = Api::new(std::default::Default::default());
+        let subreddit = sample_subreddit(&api, "wiki5");
+        let revision = |id: &str| {
+            format!(
+                "{{\"id\":\"{}\",\"page\":\"config/bot\",\"reason\":\"Update\", \
+                 \"timestamp\":1560000000.0,\"revision_hidden\":false, \
+                 \"author\":{{\"kind\":\"t2\",\"data\":{{\"name\":\"some-mod\"}}}}}}",
+                id
+            )
+        };
+        let _m1 = mock("GET", "/r/wiki5/wiki/revisions/config/bot?limit=2&show=all")
+            .with_status(200)
+            .with_body(listing(&[revision("r2"), revision("r1")]))
+            .create();
+        let _m2 = mock("POST", "/r/wiki5/api/wiki/revert")
+            .match_body(form_field("revision=r1"))
+            .with_status(200)
+            .with_body("{}")
+            .create();
+
+        let revisions = subreddit.get_wiki_revisions("config/bot", 2).unwrap();
+        subreddit
+            .revert_wiki_page("config/bot", &revisions[1].id)
+            .unwrap();
+
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0].id, "r2");
+        assert_eq!(revisions[0].author, Some(String::from("some-mod")));
+        _m1.assert();
+        _m2.assert();
+    }
+
+    #[test]
+    fn wiki_settings() {
+        let api = Api::new(std::default::Default::default());
+        let subreddit = sample_subreddit(&api, "wiki6");
+        let _m1 = mock("GET", "/r/wiki6/wiki/settings/config/bot")
+            .with_status(200)
+            .with_body(
+                "{\"kind\":\"wikipagesettings\",\"data\":{\"permlevel\":1,\"listed\":true, \
+                 \"editors\":[{\"kind\":\"t2\",\"data\":{\"name\":\"some-user\"}}]}}",
+            )
+            .create();
+        let _m2 = mock("POST", "/r/wiki6/wiki/settings/config/bot")
+            .match_body(form_field("permlevel=2"))
+            .with_status(200)
+            .with_body(
+                "{\"kind\":\"wikipagesettings\",\"data\":{\"permlevel\":2,\"listed\":false, \
+                 \"editors\":[]}}",
+            )
+            .create();
+
+        let settings = subreddit.get_wiki_settings("config/bot").unwrap();
+        let updated = subreddit
+            .update_wiki_settings("config/bot", WikiPermission::Moderators, false)
+            .unwrap();
+
+        assert_eq!(settings.permission, WikiPermission::ApprovedEditors);
+        assert_eq!(settings.editors, vec!["some-user"]);
+        assert_eq!(updated.permission, WikiPermission::Moderators);
+        assert!(!updated.listed);
+        _m1.assert();
+        _m2.assert();
+    }
+
+    #[test]
+    fn unknown_wiki_permission() {
+        let settings = serde_json::from_str::<WikiPageSettings>("{\"permlevel\":3}");
+
+        assert!(settings.is_err());
+    }
+}
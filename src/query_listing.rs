@@ -18,6 +18,50 @@
 //!     .count(12)
 //!     .show_all(false);
 //! ```
+//!
+//! Paging backwards, with custom parameters:
+//!
+//! ```rust,no_run,ignore
+//! let ql = QueryListingRequest::new("r/rust/top", 25, 2)
+//!     .params(&[("t", "week")])
+//!     .before(Some("t3_aaaaa"))
+//!     .raw_json(true);
+//! ```
+
+use crate::{Api, ApiError};
+use serde_json::Value;
+use std::collections::VecDeque;
+
+/// The time window for listings sorted by score, like "top".
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimeFilter {
+    /// The past hour.
+    Hour,
+    /// The past day.
+    Day,
+    /// The past week.
+    Week,
+    /// The past month.
+    Month,
+    /// The past year.
+    Year,
+    /// All time.
+    All,
+}
+
+impl TimeFilter {
+    /// The value to pass as the `t` parameter.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TimeFilter::Hour => "hour",
+            TimeFilter::Day => "day",
+            TimeFilter::Week => "week",
+            TimeFilter::Month => "month",
+            TimeFilter::Year => "year",
+            TimeFilter::All => "all",
+        }
+    }
+}
 
 /// Builder struct for constructing requests to a listing endpoint.
 #[derive(Clone, Debug)]
@@ -28,6 +72,8 @@ pub struct QueryListingRequest<'a> {
     pub params: &'a [(&'a str, &'a str)],
     /// The optional fullname to start at
     pub after: Option<&'a str>,
+    /// The optional fullname to page backwards from, used instead of `after`
+    pub before: Option<&'a str>,
     /// The number received so far
     pub count: u64,
     /// The number of items to get per request
@@ -36,6 +82,10 @@ pub struct QueryListingRequest<'a> {
     pub requests: u64,
     /// Wether to show all items (true) or follow hidden items settings (false)
     pub show_all: bool,
+    /// Whether to include each item's subreddit details
+    pub sr_detail: bool,
+    /// Whether to get text fields without HTML escaping ("&amp;" and friends)
+    pub raw_json: bool,
 }
 
 impl<'a> QueryListingRequest<'a> {
@@ -45,10 +95,13 @@ impl<'a> QueryListingRequest<'a> {
             path,
             params: &[],
             after: None,
+            before: None,
             count: 0,
             limit,
             requests,
             show_all: true,
+            sr_detail: false,
+            raw_json: false,
         }
     }
 
@@ -70,6 +123,12 @@ impl<'a> QueryListingRequest<'a> {
         self
     }
 
+    /// Override the `before` field.
+    pub fn before(mut self, before: Option<&'a str>) -> Self {
+        self.before = before;
+        self
+    }
+
     /// Override the `count` field.
     pub fn count(mut self, count: u64) -> Self {
         self.count = count;
@@ -93,11 +152,170 @@ impl<'a> QueryListingRequest<'a> {
         self.show_all = show_all;
         self
     }
+
+    /// Override the `sr_detail` field.
+    pub fn sr_detail(mut self, sr_detail: bool) -> Self {
+        self.sr_detail = sr_detail;
+        self
+    }
+
+    /// Override the `raw_json` field.
+    pub fn raw_json(mut self, raw_json: bool) -> Self {
+        self.raw_json = raw_json;
+        self
+    }
+
+    /// The paging state to get the first page with.
+    pub(crate) fn paging(&self) -> Paging {
+        Paging {
+            cursor: self.before.or(self.after).unwrap_or_default().to_owned(),
+            count: self.count,
+            limit: self.limit,
+            backwards: self.before.is_some(),
+            show_all: self.show_all,
+            sr_detail: self.sr_detail,
+            raw_json: self.raw_json,
+        }
+    }
+
+    /// All of the query parameters for a single page: the custom
+    /// `params` merged with the paging parameters.
+    pub(crate) fn query_params(&self, paging: &Paging) -> Vec<(String, String)> {
+        merge_params(self.params.iter().cloned(), paging.page_params())
+    }
+}
+
+/// The state of paging through a listing, taken from a
+/// [`QueryListingRequest`] and owned, so that it can be kept
+/// across requests.
+///
+/// [`QueryListingRequest`]: struct.QueryListingRequest.html
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Paging {
+    /// The fullname to continue from, or empty for the first page
+    pub(crate) cursor: String,
+    /// The number received so far
+    pub(crate) count: u64,
+    /// The number of items to get per request
+    pub(crate) limit: u64,
+    /// Whether to page backwards, with `before` instead of `after`
+    pub(crate) backwards: bool,
+    /// Wether to show all items (true) or follow hidden items settings (false)
+    pub(crate) show_all: bool,
+    /// Whether to include each item's subreddit details
+    pub(crate) sr_detail: bool,
+    /// Whether to get text fields without HTML escaping
+    pub(crate) raw_json: bool,
+}
+
+impl Paging {
+    /// The name of the parameter for the fullname to continue from,
+    /// which depends on the direction of paging.
+    pub(crate) fn cursor_param(&self) -> &'static str {
+        if self.backwards {
+            "before"
+        } else {
+            "after"
+        }
+    }
+
+    /// The listing parameters for the next page.
+    pub(crate) fn page_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![("limit", self.limit.to_string())];
+        if !self.cursor.is_empty() {
+            params.push((self.cursor_param(), self.cursor.clone()));
+        }
+        if self.count > 0 {
+            params.push(("count", format!("{}", self.count)));
+        }
+        if self.show_all {
+            params.push(("show", "all".to_owned()));
+        }
+        if self.sr_detail {
+            params.push(("sr_detail", "true".to_owned()));
+        }
+        if self.raw_json {
+            params.push(("raw_json", "1".to_owned()));
+        }
+        params
+    }
+}
+
+/// Merge custom query parameters with the listing parameters.
+///
+/// The listing parameters are set through the builder's fields, so
+/// they take precedence over custom parameters with the same name.
+pub(crate) fn merge_params<'p, I>(
+    custom: I,
+    page: Vec<(&'static str, String)>,
+) -> Vec<(String, String)>
+where
+    I: Iterator<Item = (&'p str, &'p str)>,
+{
+    let mut params: Vec<(String, String)> = custom
+        .filter(|(key, _)| !page.iter().any(|(name, _)| name == key))
+        .map(|(key, value)| (key.to_owned(), value.to_owned()))
+        .collect();
+    params.extend(page.into_iter().map(|(key, value)| (key.to_owned(), value)));
+    params
+}
+
+/// Iterator over the items of a listing endpoint, requesting
+/// pages as they are needed.
+///
+/// Created by [`Api::listing_iter`]. Yields an error and then stops
+/// if a page can't be retrieved.
+///
+/// [`Api::listing_iter`]: ../struct.Api.html#method.listing_iter
+pub struct ListingIter<'a> {
+    api: &'a Api,
+    ql: QueryListingRequest<'a>,
+    paging: Paging,
+    items: VecDeque<Value>,
+    done: bool,
+}
+
+impl<'a> ListingIter<'a> {
+    pub(crate) fn new(api: &'a Api, ql: QueryListingRequest<'a>) -> Self {
+        ListingIter {
+            api,
+            paging: ql.paging(),
+            ql,
+            items: VecDeque::new(),
+            done: false,
+        }
+    }
+}
+
+impl<'a> Iterator for ListingIter<'a> {
+    type Item = Result<Value, ApiError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(item) = self.items.pop_front() {
+            return Some(Ok(item));
+        }
+        if self.done {
+            return None;
+        }
+        match self.api.get_listing_page(&self.ql, &self.paging) {
+            Ok((items, next)) => {
+                self.paging.count += items.len() as u64;
+                self.done = items.is_empty() || next.is_none();
+                self.paging.cursor = next.unwrap_or_default();
+                self.items.extend(items);
+                self.items.pop_front().map(Ok)
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::QueryListingRequest;
+    use super::{merge_params, QueryListingRequest};
 
     #[test]
     fn simple() {
@@ -112,8 +330,11 @@ mod tests {
         assert_eq!(ql.requests, requests);
         assert_eq!(ql.params, &[]);
         assert_eq!(ql.after, None);
+        assert_eq!(ql.before, None);
         assert_eq!(ql.count, 0);
-        assert_eq!(ql.show_all, true);
+        assert!(ql.show_all);
+        assert!(!ql.sr_detail);
+        assert!(!ql.raw_json);
     }
 
     #[test]
@@ -140,4 +361,75 @@ mod tests {
         assert_eq!(ql.count, count);
         assert_eq!(ql.show_all, show_all);
     }
+
+    #[test]
+    fn page_params() {
+        let ql = QueryListingRequest::new("p", 25, 2);
+        let mut paging = ql.paging();
+
+        assert_eq!(
+            paging.page_params(),
+            vec![("limit", "25".to_owned()), ("show", "all".to_owned())]
+        );
+        paging.cursor = "t3_aaa".to_owned();
+        paging.count = 25;
+        paging.show_all = false;
+        assert_eq!(
+            paging.page_params(),
+            vec![
+                ("limit", "25".to_owned()),
+                ("after", "t3_aaa".to_owned()),
+                ("count", "25".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn page_params_before() {
+        let ql = QueryListingRequest::new("p", 25, 2)
+            .after(Some("t3_aaa"))
+            .before(Some("t3_bbb"))
+            .show_all(false)
+            .sr_detail(true)
+            .raw_json(true);
+        let mut paging = ql.paging();
+
+        assert_eq!(paging.cursor, "t3_bbb");
+        paging.cursor = "t3_ccc".to_owned();
+        assert_eq!(
+            paging.page_params(),
+            vec![
+                ("limit", "25".to_owned()),
+                ("before", "t3_ccc".to_owned()),
+                ("sr_detail", "true".to_owned()),
+                ("raw_json", "1".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn query_params() {
+        let params = [("t", "week"), ("limit", "5"), ("q", "rust")];
+        let ql = QueryListingRequest::new("p", 10, 1).params(&params);
+        let to_owned = |params: &[(&str, &str)]| -> Vec<(String, String)> {
+            params
+                .iter()
+                .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+                .collect()
+        };
+
+        assert_eq!(
+            ql.query_params(&ql.paging()),
+            to_owned(&[
+                ("t", "week"),
+                ("q", "rust"),
+                ("limit", "10"),
+                ("show", "all")
+            ])
+        );
+        assert_eq!(
+            merge_params(params.iter().cloned(), Vec::new()),
+            to_owned(&params)
+        );
+    }
 }
@@ -0,0 +1,122 @@
+//! Tracking of the API's [rate limits].
+//!
+//! Reddit returns the state of the client's request budget in
+//! the `X-Ratelimit-*` headers of each response. The [`Api`]
+//! struct keeps the latest values, waiting for the budget to
+//! reset before sending requests once it runs out.
+//!
+//! [rate limits]: https://github.com/reddit-archive/reddit/wiki/API#rules
+//! [`Api`]: ../struct.Api.html
+//!
+//! # Examples
+//!
+//! ```rust,no_run,ignore
+//! if let Some(rl) = api.get_rate_limit() {
+//!     println!("{} requests left, reset in {:?}", rl.remaining, rl.reset_in());
+//! }
+//! ```
+
+use reqwest::header::HeaderMap;
+use std::time::{Duration, Instant};
+
+/// Header holding the number of requests used in the current period.
+pub const USED_HEADER: &str = "X-Ratelimit-Used";
+/// Header holding the number of requests left in the current period.
+pub const REMAINING_HEADER: &str = "X-Ratelimit-Remaining";
+/// Header holding the number of seconds until the period ends.
+pub const RESET_HEADER: &str = "X-Ratelimit-Reset";
+
+/// The state of the request budget, as of the last response.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    /// The number of requests used in the current period
+    pub used: u64,
+    /// The number of requests left in the current period
+    pub remaining: f64,
+    /// When the current period ends
+    pub reset: Instant,
+}
+
+impl RateLimit {
+    /// Parse the rate limit headers from a response, if all present.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let get = |name: &str| headers.get(name)?.to_str().ok()?.trim().parse::<f64>().ok();
+        let used = get(USED_HEADER)?;
+        let remaining = get(REMAINING_HEADER)?;
+        let reset = get(RESET_HEADER)?;
+        Some(RateLimit {
+            used: used as u64,
+            remaining,
+            reset: Instant::now() + Duration::from_secs(reset.max(0.0) as u64),
+        })
+    }
+
+    /// How long until the current period ends.
+    pub fn reset_in(&self) -> Duration {
+        let now = Instant::now();
+        if self.reset > now {
+            self.reset - now
+        } else {
+            Duration::from_secs(0)
+        }
+    }
+
+    /// Whether the budget has run out for the current period.
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining < 1.0 && self.reset > Instant::now()
+    }
+
+    /// Take a request from the budget, ahead of its response.
+    pub(crate) fn reserve(&mut self) {
+        self.used += 1;
+        self.remaining = (self.remaining - 1.0).max(0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RateLimit, REMAINING_HEADER, RESET_HEADER, USED_HEADER};
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(USED_HEADER, HeaderValue::from_static("4"));
+        headers.insert(REMAINING_HEADER, HeaderValue::from_static("596.0"));
+        headers.insert(RESET_HEADER, HeaderValue::from_static("300"));
+
+        let rl = RateLimit::from_headers(&headers).unwrap();
+
+        assert_eq!(rl.used, 4);
+        assert_eq!(rl.remaining, 596.0);
+        assert!(rl.reset_in() > Duration::from_secs(290));
+        assert!(!rl.is_exhausted());
+    }
+
+    #[test]
+    fn from_headers_missing() {
+        let mut headers = HeaderMap::new();
+        headers.insert(USED_HEADER, HeaderValue::from_static("4"));
+
+        assert_eq!(RateLimit::from_headers(&headers), None);
+    }
+
+    #[test]
+    fn exhausted() {
+        let mut rl = RateLimit {
+            used: 599,
+            remaining: 1.0,
+            reset: Instant::now() + Duration::from_secs(60),
+        };
+        assert!(!rl.is_exhausted());
+
+        rl.reserve();
+        assert_eq!(rl.used, 600);
+        assert!(rl.is_exhausted());
+
+        rl.reset = Instant::now();
+        assert!(!rl.is_exhausted());
+        assert_eq!(rl.reset_in(), Duration::from_secs(0));
+    }
+}
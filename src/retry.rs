@@ -0,0 +1,205 @@
+//! Retrying of requests that fail for transient reasons.
+//!
+//! Reddit's API frequently responds with server errors when under
+//! load. The [`Api`] struct retries those requests according to its
+//! [`RetryPolicy`], waiting longer between each attempt.
+//!
+//! [`Api`]: ../struct.Api.html
+//! [`RetryPolicy`]: struct.RetryPolicy.html
+//!
+//! # Examples
+//!
+//! ```rust,no_run,ignore
+//! let policy = RetryPolicy::new(5)
+//!     .base_delay(Duration::from_millis(500))
+//!     .max_delay(Duration::from_secs(10));
+//! api.set_retry_policy(policy);
+//! ```
+
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+use std::time::Duration;
+
+/// Builder struct for configuring how failed requests are retried.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// The total number of attempts to make, including the first
+    pub max_attempts: u32,
+    /// The delay before the first retry, doubled for each one after
+    pub base_delay: Duration,
+    /// The longest to wait between attempts
+    pub max_delay: Duration,
+    /// Whether to randomize the delays, so that clients don't retry in lockstep
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::new(3)
+    }
+}
+
+impl RetryPolicy {
+    /// Construct a new builder.
+    pub fn new(max_attempts: u32) -> Self {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+        }
+    }
+
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        RetryPolicy::new(1)
+    }
+
+    /// Override the `max_attempts` field.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Override the `base_delay` field.
+    pub fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Override the `max_delay` field.
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Override the `jitter` field.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Whether a response with the status should be retried.
+    pub fn is_retryable(status: StatusCode) -> bool {
+        matches!(
+            status,
+            StatusCode::TOO_MANY_REQUESTS
+                | StatusCode::INTERNAL_SERVER_ERROR
+                | StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+                | StatusCode::GATEWAY_TIMEOUT
+        )
+    }
+
+    /// How long to wait before the next attempt, after `attempt`
+    /// attempts have failed.
+    ///
+    /// The response's `Retry-After` header, if any, takes precedence.
+    pub fn delay(&self, attempt: u32, headers: Option<&HeaderMap>) -> Duration {
+        if let Some(retry_after) = headers.and_then(parse_retry_after) {
+            return retry_after;
+        }
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self
+            .base_delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |d| d.min(self.max_delay));
+        if self.jitter {
+            let half = delay / 2;
+            let extra = rand::thread_rng().gen_range(0, half.as_nanos() as u64 + 1);
+            half + Duration::from_nanos(extra)
+        } else {
+            delay
+        }
+    }
+}
+
+/// Parse the `Retry-After` header, when given in seconds.
+pub(crate) fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let seconds = headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RetryPolicy;
+    use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+    use reqwest::StatusCode;
+    use std::time::Duration;
+
+    #[test]
+    fn simple() {
+        let policy = RetryPolicy::default();
+
+        assert_eq!(policy.max_attempts, 3);
+        assert_eq!(policy.base_delay, Duration::from_secs(1));
+        assert_eq!(policy.max_delay, Duration::from_secs(30));
+        assert!(policy.jitter);
+        assert_eq!(RetryPolicy::none().max_attempts, 1);
+    }
+
+    #[test]
+    fn with_builders() {
+        let policy = RetryPolicy::new(1)
+            .max_attempts(5)
+            .base_delay(Duration::from_millis(10))
+            .max_delay(Duration::from_millis(50))
+            .jitter(false);
+
+        assert_eq!(policy.max_attempts, 5);
+        assert_eq!(policy.base_delay, Duration::from_millis(10));
+        assert_eq!(policy.max_delay, Duration::from_millis(50));
+        assert!(!policy.jitter);
+    }
+
+    #[test]
+    fn retryable_statuses() {
+        assert!(RetryPolicy::is_retryable(StatusCode::TOO_MANY_REQUESTS));
+        assert!(RetryPolicy::is_retryable(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!RetryPolicy::is_retryable(StatusCode::NOT_FOUND));
+        assert!(!RetryPolicy::is_retryable(StatusCode::OK));
+    }
+
+    #[test]
+    fn exponential_delay() {
+        let policy = RetryPolicy::new(5)
+            .base_delay(Duration::from_millis(10))
+            .max_delay(Duration::from_millis(50))
+            .jitter(false);
+
+        assert_eq!(policy.delay(1, None), Duration::from_millis(10));
+        assert_eq!(policy.delay(2, None), Duration::from_millis(20));
+        assert_eq!(policy.delay(3, None), Duration::from_millis(40));
+        assert_eq!(policy.delay(4, None), Duration::from_millis(50));
+        assert_eq!(policy.delay(40, None), Duration::from_millis(50));
+    }
+
+    #[test]
+    fn jittered_delay() {
+        let policy = RetryPolicy::new(5).base_delay(Duration::from_millis(100));
+
+        for _ in 0..20 {
+            let delay = policy.delay(1, None);
+            assert!(delay >= Duration::from_millis(50));
+            assert!(delay <= Duration::from_millis(100));
+        }
+    }
+
+    #[test]
+    fn retry_after_delay() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+
+        assert_eq!(
+            RetryPolicy::default().delay(1, Some(&headers)),
+            Duration::from_secs(7)
+        );
+    }
+}